@@ -0,0 +1,39 @@
+use super::process::{
+    find_processes, get_running_apps, kill_matching, kill_process, refresh_system, KillReport,
+    ProcessError, ProcessInfo, ProcessMatcher, TerminateOptions,
+};
+
+/// Finding and terminating blocked applications.
+pub trait AppBlocker: Send + Sync {
+    /// Takes a fresh snapshot before a kill, so stale pids aren't signalled.
+    fn refresh(&self) -> Result<(), ProcessError>;
+    fn find(&self, matcher: &ProcessMatcher) -> Result<Vec<ProcessInfo>, ProcessError>;
+    fn running(&self, matchers: &[ProcessMatcher]) -> Result<Vec<ProcessInfo>, ProcessError>;
+    fn kill_pid(&self, pid: u32, options: &TerminateOptions) -> Result<KillReport, ProcessError>;
+    fn kill_matching(&self, matchers: &[ProcessMatcher], options: &TerminateOptions) -> Result<KillReport, ProcessError>;
+}
+
+/// The real implementation backed by the sysinfo process worker.
+pub struct SystemAppBlocker;
+
+impl AppBlocker for SystemAppBlocker {
+    fn refresh(&self) -> Result<(), ProcessError> {
+        refresh_system()
+    }
+
+    fn find(&self, matcher: &ProcessMatcher) -> Result<Vec<ProcessInfo>, ProcessError> {
+        find_processes(matcher)
+    }
+
+    fn running(&self, matchers: &[ProcessMatcher]) -> Result<Vec<ProcessInfo>, ProcessError> {
+        get_running_apps(matchers)
+    }
+
+    fn kill_pid(&self, pid: u32, options: &TerminateOptions) -> Result<KillReport, ProcessError> {
+        kill_process(pid, options)
+    }
+
+    fn kill_matching(&self, matchers: &[ProcessMatcher], options: &TerminateOptions) -> Result<KillReport, ProcessError> {
+        kill_matching(matchers, options)
+    }
+}
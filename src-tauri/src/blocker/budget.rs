@@ -0,0 +1,237 @@
+use super::catalog::expand_app_ids;
+use super::process::{ProcessError, ProcessMatcher};
+use super::rules::DomainRule;
+use chrono::{DateTime, Duration, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fs;
+use std::path::PathBuf;
+use thiserror::Error;
+
+pub const DEFAULT_SAMPLE_INTERVAL_SECS: u64 = 15;
+pub const MIN_SAMPLE_INTERVAL_SECS: u64 = 5;
+pub const MAX_SAMPLE_INTERVAL_SECS: u64 = 300;
+
+#[derive(Error, Debug)]
+pub enum BudgetError {
+    #[error("Invalid budget '{0}': {1}")]
+    InvalidBudget(String, String),
+    #[error("Invalid budget settings: {0}")]
+    InvalidSettings(String),
+    #[error("Process error: {0}")]
+    Process(#[from] ProcessError),
+    #[error("Budget config error: {0}")]
+    Config(#[from] std::io::Error),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BudgetTarget {
+    /// An app catalog id; every executable of the app counts.
+    App { app_id: String },
+    Process { matcher: ProcessMatcher },
+    /// A website rule. Usage is reported by the frontend or a browser extension.
+    Site { rule: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Budget {
+    pub id: String,
+    pub target: BudgetTarget,
+    pub daily_minutes: u64,
+    /// Percentages of the allowance at which a warning event is sent.
+    #[serde(default = "default_warn_at")]
+    pub warn_at_percent: Vec<u8>,
+}
+
+fn default_warn_at() -> Vec<u8> {
+    vec![50, 80, 95]
+}
+
+impl Budget {
+    pub fn limit_seconds(&self) -> u64 {
+        self.daily_minutes * 60
+    }
+
+    /// Matchers for app and process budgets; site budgets have none.
+    pub fn matchers(&self) -> Result<Vec<ProcessMatcher>, ProcessError> {
+        match &self.target {
+            BudgetTarget::App { app_id } => expand_app_ids(std::slice::from_ref(app_id)),
+            BudgetTarget::Process { matcher } => Ok(vec![matcher.clone()]),
+            BudgetTarget::Site { .. } => Ok(Vec::new()),
+        }
+    }
+
+    pub fn site_rule(&self) -> Option<DomainRule> {
+        match &self.target {
+            BudgetTarget::Site { rule } => DomainRule::parse(rule),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BudgetConfig {
+    /// Hour (local time) at which daily allowances reset, e.g. 4 for 04:00.
+    pub day_start_hour: u32,
+    pub sample_interval_secs: u64,
+    pub budgets: Vec<Budget>,
+}
+
+impl Default for BudgetConfig {
+    fn default() -> Self {
+        BudgetConfig {
+            day_start_hour: 0,
+            sample_interval_secs: DEFAULT_SAMPLE_INTERVAL_SECS,
+            budgets: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BudgetStatus {
+    pub budget: Budget,
+    pub used_seconds: u64,
+    pub limit_seconds: u64,
+    pub remaining_seconds: u64,
+    pub percent: u64,
+    pub exhausted: bool,
+}
+
+impl BudgetStatus {
+    pub fn new(budget: &Budget, used_seconds: u64) -> Self {
+        let limit_seconds = budget.limit_seconds();
+        let percent = (used_seconds * 100).checked_div(limit_seconds).unwrap_or(100);
+        BudgetStatus {
+            budget: budget.clone(),
+            used_seconds,
+            limit_seconds,
+            remaining_seconds: limit_seconds.saturating_sub(used_seconds),
+            percent,
+            exhausted: used_seconds >= limit_seconds,
+        }
+    }
+
+    /// The highest configured warning threshold reached, if any.
+    pub fn warning_level(&self) -> Option<u8> {
+        self.budget
+            .warn_at_percent
+            .iter()
+            .copied()
+            .filter(|p| u64::from(*p) <= self.percent)
+            .max()
+    }
+}
+
+/// Enforcement bookkeeping for the current budget day.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BudgetState {
+    pub day: String,
+    /// Highest warning threshold already reported per budget id.
+    pub warned: BTreeMap<String, u8>,
+    /// Budgets whose exhaustion was already reported.
+    pub exhausted: BTreeSet<String>,
+    /// Site rules that were added by budgets, as opposed to the user's own block list.
+    pub blocked_sites: BTreeSet<String>,
+}
+
+fn get_data_path(file: &str) -> PathBuf {
+    let path = dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("FocusKeeper")
+        .join(file);
+
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    path
+}
+
+pub fn load_budget_config() -> BudgetConfig {
+    fs::read_to_string(get_data_path("budgets.json"))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// Validates and normalizes `config` (site rules are rewritten to their canonical form), then saves it.
+pub fn save_budget_config(mut config: BudgetConfig) -> Result<BudgetConfig, BudgetError> {
+    if config.day_start_hour > 23 {
+        return Err(BudgetError::InvalidSettings("day_start_hour must be between 0 and 23".to_string()));
+    }
+    if !(MIN_SAMPLE_INTERVAL_SECS..=MAX_SAMPLE_INTERVAL_SECS).contains(&config.sample_interval_secs) {
+        return Err(BudgetError::InvalidSettings(format!(
+            "sample_interval_secs must be between {} and {}",
+            MIN_SAMPLE_INTERVAL_SECS, MAX_SAMPLE_INTERVAL_SECS
+        )));
+    }
+
+    let mut ids = HashSet::new();
+    for budget in &mut config.budgets {
+        let invalid = |reason: &str| BudgetError::InvalidBudget(budget.id.clone(), reason.to_string());
+        if budget.id.trim().is_empty() {
+            return Err(invalid("id must not be empty"));
+        }
+        if !ids.insert(budget.id.clone()) {
+            return Err(invalid("duplicate id"));
+        }
+        if budget.daily_minutes > 24 * 60 {
+            return Err(invalid("daily_minutes must not exceed a day"));
+        }
+        if budget.warn_at_percent.iter().any(|p| *p == 0 || *p > 100) {
+            return Err(invalid("warning thresholds must be between 1 and 100"));
+        }
+
+        match &mut budget.target {
+            BudgetTarget::Site { rule } => {
+                *rule = DomainRule::parse(rule)
+                    .ok_or_else(|| invalid("not a valid domain rule"))?
+                    .to_string();
+            }
+            BudgetTarget::Process { matcher } => {
+                matcher.compile()?;
+            }
+            BudgetTarget::App { app_id } => {
+                expand_app_ids(std::slice::from_ref(app_id))?;
+            }
+        }
+        budget.warn_at_percent.sort_unstable();
+        budget.warn_at_percent.dedup();
+    }
+
+    let content = serde_json::to_string_pretty(&config).map_err(std::io::Error::from)?;
+    fs::write(get_data_path("budgets.json"), content)?;
+    Ok(config)
+}
+
+pub fn load_budget_state() -> BudgetState {
+    fs::read_to_string(get_data_path("budget-state.json"))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_budget_state(state: &BudgetState) -> std::io::Result<()> {
+    let content = serde_json::to_string_pretty(state)?;
+    fs::write(get_data_path("budget-state.json"), content)
+}
+
+/// The budget day `now` falls in: days start at `day_start_hour` instead of midnight,
+/// so 02:00 with a 04:00 boundary still counts towards the previous date.
+pub fn budget_day(now: DateTime<Local>, day_start_hour: u32) -> String {
+    (now - Duration::hours(i64::from(day_start_hour)))
+        .format("%Y-%m-%d")
+        .to_string()
+}
+
+/// Ids of the site budgets whose rule covers `domain`.
+pub fn site_budgets_for(config: &BudgetConfig, domain: &str) -> Vec<String> {
+    config
+        .budgets
+        .iter()
+        .filter(|b| b.site_rule().map(|rule| rule.matches(domain)).unwrap_or(false))
+        .map(|b| b.id.clone())
+        .collect()
+}
@@ -0,0 +1,582 @@
+use super::process::{MatchMode, ProcessError, ProcessMatcher};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+
+static CATALOG: Lazy<Mutex<Option<Vec<AppEntry>>>> = Lazy::new(|| Mutex::new(None));
+
+// 一些应用的主进程之外还有常驻的辅助进程，只杀主进程会被立刻拉起
+const KNOWN_HELPERS: &[(&str, &[&str])] = &[
+    ("steam", &["steamwebhelper", "steamservice", "steam_osx"]),
+    ("discord", &["discord", "discordptb", "discordcanary"]),
+    ("chrome", &["chrome", "chrome_crashpad_handler"]),
+    ("google-chrome", &["chrome", "chrome_crashpad_handler"]),
+    ("slack", &["slack"]),
+    ("spotify", &["spotify", "spotifywebhelper"]),
+    ("epicgameslauncher", &["epicgameslauncher", "epicwebhelper"]),
+    ("battle.net", &["battle.net"]),
+];
+
+// 启动器、shell 和解释器本身不能作为应用的匹配条件，否则会误杀用户的终端和脚本
+const LAUNCHERS: &[&str] = &[
+    "env", "sh", "bash", "dash", "zsh", "fish", "ksh", "csh", "tcsh", "python", "python2", "python3", "perl",
+    "ruby", "node", "nodejs", "java", "javaw", "mono", "dotnet", "wine", "flatpak", "snap", "cmd", "powershell",
+    "pwsh", "wscript", "cscript", "rundll32", "open", "osascript",
+];
+
+fn is_launcher(stem: &str) -> bool {
+    // python3.12、perl5.36 这类带版本号的解释器名同样算启动器
+    let base = stem.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+    LAUNCHERS.contains(&stem) || LAUNCHERS.contains(&base)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppEntry {
+    /// Stable id such as `desktop:org.telegram.desktop`, `macos:com.hnc.Discord` or `windows:Steam`.
+    pub id: String,
+    pub name: String,
+    /// Process names (without `.exe`) that belong to this app.
+    pub executables: Vec<String>,
+    /// Absolute executable paths, when known.
+    pub exe_paths: Vec<String>,
+    /// Icon file path, when it could be resolved.
+    pub icon: Option<String>,
+}
+
+impl AppEntry {
+    pub fn matchers(&self) -> Vec<ProcessMatcher> {
+        let mut matchers: Vec<ProcessMatcher> = self
+            .executables
+            .iter()
+            .map(|name| ProcessMatcher::new(MatchMode::Exact, name.clone()))
+            .collect();
+        matchers.extend(
+            self.exe_paths
+                .iter()
+                .map(|path| ProcessMatcher::new(MatchMode::ExePath, path.clone())),
+        );
+        matchers
+    }
+}
+
+fn lock_catalog() -> MutexGuard<'static, Option<Vec<AppEntry>>> {
+    CATALOG.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn exe_stem(path: &str) -> Option<String> {
+    let file = Path::new(path.trim_matches('"')).file_name()?.to_string_lossy().to_lowercase();
+    let stem = file.strip_suffix(".exe").unwrap_or(&file).to_string();
+    if stem.is_empty() {
+        None
+    } else {
+        Some(stem)
+    }
+}
+
+fn finish_entry(mut entry: AppEntry) -> AppEntry {
+    let mut extra = Vec::new();
+    for exe in &entry.executables {
+        if let Some((_, helpers)) = KNOWN_HELPERS.iter().find(|(name, _)| name == exe) {
+            extra.extend(helpers.iter().map(|h| h.to_string()));
+        }
+    }
+    entry.executables.extend(extra);
+    entry.executables.retain(|exe| !is_launcher(exe));
+    entry
+        .exe_paths
+        .retain(|path| exe_stem(path).is_some_and(|stem| !is_launcher(&stem)));
+    entry.executables.sort();
+    entry.executables.dedup();
+    entry.exe_paths.sort();
+    entry.exe_paths.dedup();
+    entry
+}
+
+/// Scans installed applications, or returns the cached result unless `refresh` is set.
+pub fn get_catalog(refresh: bool) -> Vec<AppEntry> {
+    let mut catalog = lock_catalog();
+    if refresh || catalog.is_none() {
+        let mut by_id: BTreeMap<String, AppEntry> = BTreeMap::new();
+        for entry in scan_installed_apps() {
+            let entry = finish_entry(entry);
+            if !entry.executables.is_empty() || !entry.exe_paths.is_empty() {
+                by_id.entry(entry.id.clone()).or_insert(entry);
+            }
+        }
+        let mut entries: Vec<AppEntry> = by_id.into_values().collect();
+        entries.sort_by_key(|e| e.name.to_lowercase());
+        *catalog = Some(entries);
+    }
+    catalog.clone().unwrap_or_default()
+}
+
+pub fn find_app(id: &str) -> Option<AppEntry> {
+    get_catalog(false).into_iter().find(|entry| entry.id == id)
+}
+
+/// Expands catalog ids into process matchers for every executable of each app.
+pub fn expand_app_ids(ids: &[String]) -> Result<Vec<ProcessMatcher>, ProcessError> {
+    let catalog = get_catalog(false);
+    let mut matchers = Vec::new();
+    for id in ids {
+        let entry = catalog
+            .iter()
+            .find(|entry| &entry.id == id)
+            .ok_or_else(|| ProcessError::UnknownApp(id.clone()))?;
+        matchers.extend(entry.matchers());
+    }
+    Ok(matchers)
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn scan_installed_apps() -> Vec<AppEntry> {
+    let mut app_dirs = vec![
+        PathBuf::from("/usr/share/applications"),
+        PathBuf::from("/usr/local/share/applications"),
+        PathBuf::from("/var/lib/flatpak/exports/share/applications"),
+        PathBuf::from("/var/lib/snapd/desktop/applications"),
+    ];
+    if let Some(data) = dirs::data_dir() {
+        app_dirs.push(data.join("applications"));
+        app_dirs.push(data.join("flatpak/exports/share/applications"));
+    }
+
+    let mut entries = Vec::new();
+    for dir in app_dirs {
+        let read_dir = match fs::read_dir(&dir) {
+            Ok(read_dir) => read_dir,
+            Err(_) => continue,
+        };
+        for file in read_dir.flatten() {
+            let path = file.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("desktop") {
+                continue;
+            }
+            if let Some(entry) = parse_desktop_file(&path) {
+                entries.push(entry);
+            }
+        }
+    }
+    entries
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn parse_desktop_file(path: &Path) -> Option<AppEntry> {
+    let content = fs::read_to_string(path).ok()?;
+    let mut fields: BTreeMap<&str, &str> = BTreeMap::new();
+    let mut in_main_section = false;
+
+    for line in content.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            in_main_section = line == "[Desktop Entry]";
+            continue;
+        }
+        if !in_main_section {
+            continue;
+        }
+        if let Some((key, value)) = line.split_once('=') {
+            fields.entry(key.trim()).or_insert(value.trim());
+        }
+    }
+
+    if fields.get("Type") != Some(&"Application")
+        || fields.get("NoDisplay") == Some(&"true")
+        || fields.get("Hidden") == Some(&"true")
+    {
+        return None;
+    }
+
+    let name = fields.get("Name")?.to_string();
+    let exec = fields.get("Exec")?;
+    let mut executables = Vec::new();
+    let mut exe_paths = Vec::new();
+
+    if let Some(program) = desktop_exec_program(exec) {
+        if program.starts_with('/') {
+            exe_paths.push(program.clone());
+        }
+        executables.extend(exe_stem(&program));
+    }
+    if let Some(class) = fields.get("StartupWMClass") {
+        executables.push(class.to_lowercase());
+    }
+
+    let file_id = path.file_stem()?.to_string_lossy().to_string();
+    Some(AppEntry {
+        id: format!("desktop:{}", file_id),
+        name,
+        executables,
+        exe_paths,
+        icon: fields.get("Icon").and_then(|icon| resolve_icon(icon)),
+    })
+}
+
+/// Picks the real program out of an `Exec=` line, skipping `env` assignments,
+/// unwrapping `sh -c '...'` and `flatpak run --command=...`, and taking the script
+/// rather than the interpreter for `python3 script.py` style launchers.
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn desktop_exec_program(exec: &str) -> Option<String> {
+    exec_program(&split_exec(exec), 0)
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn exec_program(tokens: &[String], depth: usize) -> Option<String> {
+    // sh -c 里可能再套 env 或 sh -c，限制层数
+    if depth > 4 {
+        return None;
+    }
+    let mut rest = tokens.iter().map(String::as_str).filter(|t| !t.starts_with('%'));
+    let program = rest.next()?;
+    let stem = exe_stem(program)?;
+    let rest: Vec<&str> = rest.collect();
+
+    match stem.as_str() {
+        "env" => {
+            let index = rest.iter().position(|t| !t.contains('=') && !t.starts_with('-'))?;
+            exec_program(&to_owned(&rest[index..]), depth + 1)
+        }
+        "sh" | "bash" | "dash" | "zsh" if rest.iter().any(|t| is_command_flag(t)) => {
+            let index = rest.iter().position(|t| is_command_flag(t))?;
+            // 只取最后一条命令，如 "cd /opt/app && exec ./app" -> ./app
+            let script = rest.get(index + 1)?;
+            let last = script.rsplit([';', '&', '|']).find(|part| !part.trim().is_empty())?;
+            let mut inner = split_exec(last);
+            if inner.first().map(String::as_str) == Some("exec") {
+                inner.remove(0);
+            }
+            exec_program(&inner, depth + 1)
+        }
+        "flatpak" => {
+            if let Some(command) = rest.iter().find_map(|t| t.strip_prefix("--command=")) {
+                return Some(command.to_string());
+            }
+            // 没有 --command 时取应用 ID 最后一段，如 com.discordapp.Discord -> discord
+            let app_id = rest.iter().rev().find(|t| !t.starts_with('-'))?;
+            app_id.rsplit('.').next().map(|s| s.to_lowercase())
+        }
+        "java" => {
+            // java -jar app.jar 的进程名仍是 java，没有可用于区分的程序名
+            None
+        }
+        _ if is_launcher(&stem) => {
+            // 解释器启动的脚本，进程名通常就是脚本名（如 python3 /usr/bin/foo -> foo）
+            // -m module、-c code 这类没有独立的脚本名，放弃
+            if rest.iter().any(|t| matches!(*t, "-m" | "-c" | "-e")) {
+                return None;
+            }
+            let script = rest.iter().find(|t| !t.starts_with('-'))?;
+            exe_stem(script).filter(|s| !is_launcher(s)).map(|_| script.to_string())
+        }
+        _ => Some(program.to_string()),
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn is_command_flag(token: &str) -> bool {
+    // -c 或 -lc、-ec 这类合并写法
+    token.len() > 1 && token.starts_with('-') && !token.starts_with("--") && token.ends_with('c')
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn to_owned(tokens: &[&str]) -> Vec<String> {
+    tokens.iter().map(|t| t.to_string()).collect()
+}
+
+/// Splits a command line the way the desktop entry spec quotes it: whitespace
+/// separates arguments, single and double quotes group them.
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn split_exec(exec: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut quote: Option<char> = None;
+    let mut has_token = false;
+    let mut chars = exec.chars();
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some('"'), '\\') => current.extend(chars.next()),
+            (Some(_), c) => current.push(c),
+            (None, '"') | (None, '\'') => {
+                quote = Some(c);
+                has_token = true;
+            }
+            (None, '\\') => current.extend(chars.next()),
+            (None, c) if c.is_whitespace() => {
+                if has_token || !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+                has_token = false;
+            }
+            (None, c) => current.push(c),
+        }
+    }
+    if has_token || !current.is_empty() {
+        tokens.push(current);
+    }
+    tokens
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn resolve_icon(icon: &str) -> Option<String> {
+    if icon.starts_with('/') {
+        return Some(icon.to_string());
+    }
+
+    let mut roots = vec![PathBuf::from("/usr/share/icons/hicolor")];
+    if let Some(data) = dirs::data_dir() {
+        roots.insert(0, data.join("icons/hicolor"));
+    }
+    roots.push(PathBuf::from("/var/lib/flatpak/exports/share/icons/hicolor"));
+
+    for root in &roots {
+        for size in ["scalable", "256x256", "128x128", "64x64", "48x48"] {
+            for ext in ["svg", "png"] {
+                let candidate = root.join(size).join("apps").join(format!("{}.{}", icon, ext));
+                if candidate.exists() {
+                    return Some(candidate.to_string_lossy().to_string());
+                }
+            }
+        }
+    }
+
+    ["png", "svg", "xpm"]
+        .iter()
+        .map(|ext| PathBuf::from("/usr/share/pixmaps").join(format!("{}.{}", icon, ext)))
+        .find(|candidate| candidate.exists())
+        .map(|candidate| candidate.to_string_lossy().to_string())
+}
+
+#[cfg(target_os = "macos")]
+fn scan_installed_apps() -> Vec<AppEntry> {
+    let mut app_dirs = vec![
+        PathBuf::from("/Applications"),
+        PathBuf::from("/System/Applications"),
+    ];
+    if let Some(home) = dirs::home_dir() {
+        app_dirs.push(home.join("Applications"));
+    }
+
+    let mut entries = Vec::new();
+    for dir in app_dirs {
+        let read_dir = match fs::read_dir(&dir) {
+            Ok(read_dir) => read_dir,
+            Err(_) => continue,
+        };
+        for bundle in read_dir.flatten() {
+            let path = bundle.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("app") {
+                continue;
+            }
+            if let Some(entry) = parse_app_bundle(&path) {
+                entries.push(entry);
+            }
+        }
+    }
+    entries
+}
+
+#[cfg(target_os = "macos")]
+fn parse_app_bundle(bundle: &Path) -> Option<AppEntry> {
+    use std::process::Command;
+
+    let plist = bundle.join("Contents/Info.plist");
+    // Info.plist 可能是二进制格式，交给 plutil 转成 JSON
+    let output = Command::new("plutil")
+        .args(["-convert", "json", "-o", "-"])
+        .arg(&plist)
+        .output()
+        .ok()?;
+    let info: serde_json::Value = serde_json::from_slice(&output.stdout).ok()?;
+    let get = |key: &str| info.get(key).and_then(|v| v.as_str()).map(|s| s.to_string());
+
+    let executable = get("CFBundleExecutable")?;
+    let name = get("CFBundleDisplayName")
+        .or_else(|| get("CFBundleName"))
+        .or_else(|| bundle.file_stem().map(|s| s.to_string_lossy().to_string()))?;
+    let id = get("CFBundleIdentifier").unwrap_or_else(|| name.clone());
+    let exe_path = bundle.join("Contents/MacOS").join(&executable);
+
+    let icon = get("CFBundleIconFile").map(|file| {
+        let file = if file.ends_with(".icns") { file } else { format!("{}.icns", file) };
+        bundle.join("Contents/Resources").join(file)
+    });
+
+    Some(AppEntry {
+        id: format!("macos:{}", id),
+        name,
+        executables: vec![executable.to_lowercase()],
+        exe_paths: vec![exe_path.to_string_lossy().to_string()],
+        icon: icon
+            .filter(|path| path.exists())
+            .map(|path| path.to_string_lossy().to_string()),
+    })
+}
+
+#[cfg(target_os = "windows")]
+fn scan_installed_apps() -> Vec<AppEntry> {
+    const UNINSTALL_KEYS: &[&str] = &[
+        r"HKLM\SOFTWARE\Microsoft\Windows\CurrentVersion\Uninstall",
+        r"HKLM\SOFTWARE\WOW6432Node\Microsoft\Windows\CurrentVersion\Uninstall",
+        r"HKCU\SOFTWARE\Microsoft\Windows\CurrentVersion\Uninstall",
+    ];
+
+    let mut entries = Vec::new();
+    for key in UNINSTALL_KEYS {
+        for (subkey, values) in query_registry(key) {
+            if let Some(entry) = parse_uninstall_entry(&subkey, &values) {
+                entries.push(entry);
+            }
+        }
+    }
+    entries.extend(scan_start_menu());
+    entries
+}
+
+/// Runs `reg query <key> /s` and groups the values by subkey.
+#[cfg(target_os = "windows")]
+fn query_registry(key: &str) -> Vec<(String, BTreeMap<String, String>)> {
+    use std::process::Command;
+
+    let output = match Command::new("reg").args(["query", key, "/s"]).output() {
+        Ok(output) => output,
+        Err(_) => return Vec::new(),
+    };
+    let text = String::from_utf8_lossy(&output.stdout);
+
+    let mut groups = Vec::new();
+    let mut current: Option<(String, BTreeMap<String, String>)> = None;
+    for line in text.lines() {
+        if line.starts_with("HKEY_") {
+            if let Some(group) = current.take() {
+                groups.push(group);
+            }
+            let subkey = line.rsplit('\\').next().unwrap_or(line).to_string();
+            current = Some((subkey, BTreeMap::new()));
+            continue;
+        }
+        let parts: Vec<&str> = line.trim().splitn(3, "    ").collect();
+        if let (Some((_, values)), [name, _kind, value]) = (current.as_mut(), parts.as_slice()) {
+            values.insert(name.to_string(), value.trim().to_string());
+        }
+    }
+    if let Some(group) = current {
+        groups.push(group);
+    }
+    groups
+}
+
+#[cfg(target_os = "windows")]
+fn parse_uninstall_entry(subkey: &str, values: &BTreeMap<String, String>) -> Option<AppEntry> {
+    let name = values.get("DisplayName")?.clone();
+    if values.get("SystemComponent").map(|v| v == "0x1").unwrap_or(false) {
+        return None;
+    }
+
+    // DisplayIcon 通常是 "C:\...\app.exe,0" 形式，同时给出了图标和可执行文件
+    let icon = values
+        .get("DisplayIcon")
+        .map(|icon| icon.split(',').next().unwrap_or(icon).trim_matches('"').to_string());
+
+    let mut exe_paths = Vec::new();
+    if let Some(icon) = &icon {
+        if icon.to_lowercase().ends_with(".exe") && !icon.to_lowercase().contains("unins") {
+            exe_paths.push(icon.clone());
+        }
+    }
+    let executables = exe_paths.iter().filter_map(|p| exe_stem(p)).collect();
+
+    Some(AppEntry {
+        id: format!("windows:{}", subkey),
+        name,
+        executables,
+        exe_paths,
+        icon,
+    })
+}
+
+/// Start Menu shortcuts carry the friendly name; the `.lnk` target path is stored
+/// as a NUL-terminated string in the LinkInfo block, which is all we need.
+#[cfg(target_os = "windows")]
+fn scan_start_menu() -> Vec<AppEntry> {
+    let mut roots = Vec::new();
+    if let Ok(program_data) = std::env::var("ProgramData") {
+        roots.push(PathBuf::from(program_data).join(r"Microsoft\Windows\Start Menu\Programs"));
+    }
+    if let Ok(app_data) = std::env::var("APPDATA") {
+        roots.push(PathBuf::from(app_data).join(r"Microsoft\Windows\Start Menu\Programs"));
+    }
+
+    let mut entries = Vec::new();
+    let mut stack = roots;
+    while let Some(dir) = stack.pop() {
+        let read_dir = match fs::read_dir(&dir) {
+            Ok(read_dir) => read_dir,
+            Err(_) => continue,
+        };
+        for item in read_dir.flatten() {
+            let path = item.path();
+            if path.is_dir() {
+                stack.push(path);
+                continue;
+            }
+            if path.extension().and_then(|e| e.to_str()) != Some("lnk") {
+                continue;
+            }
+            let target = match lnk_target(&path) {
+                Some(target) => target,
+                None => continue,
+            };
+            let name = match path.file_stem() {
+                Some(stem) => stem.to_string_lossy().to_string(),
+                None => continue,
+            };
+            entries.push(AppEntry {
+                id: format!("windows:{}", name),
+                name,
+                executables: exe_stem(&target).into_iter().collect(),
+                exe_paths: vec![target.clone()],
+                icon: Some(target),
+            });
+        }
+    }
+    entries
+}
+
+#[cfg(target_os = "windows")]
+fn lnk_target(path: &Path) -> Option<String> {
+    let data = fs::read(path).ok()?;
+    let read_u16 = |offset: usize| -> Option<usize> {
+        Some(u16::from_le_bytes([*data.get(offset)?, *data.get(offset + 1)?]) as usize)
+    };
+    let read_u32 = |offset: usize| -> Option<usize> {
+        let bytes = data.get(offset..offset + 4)?;
+        Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize)
+    };
+
+    let flags = read_u32(0x14)?;
+    let has_id_list = flags & 0x1 != 0;
+    let has_link_info = flags & 0x2 != 0;
+    if !has_link_info {
+        return None;
+    }
+
+    let mut offset = 0x4c;
+    if has_id_list {
+        offset += 2 + read_u16(offset)?;
+    }
+    let local_base_path_offset = read_u32(offset + 0x10)?;
+    let start = offset + local_base_path_offset;
+    let end = start + data.get(start..)?.iter().position(|b| *b == 0)?;
+    let target = String::from_utf8_lossy(&data[start..end]).to_string();
+
+    if target.to_lowercase().ends_with(".exe") {
+        Some(target)
+    } else {
+        None
+    }
+}
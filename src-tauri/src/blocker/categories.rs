@@ -0,0 +1,173 @@
+use super::rules::DomainRule;
+use super::website::{BlockerError, WebsiteBlocker};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::PathBuf;
+
+/// A curated blocklist shipped with the app. Bump `version` whenever `domains` changes
+/// so enabled categories are re-applied on upgrade.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Category {
+    pub id: String,
+    pub name: String,
+    pub version: u32,
+    pub domains: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CategoryInfo {
+    pub id: String,
+    pub name: String,
+    pub version: u32,
+    pub domains: Vec<String>,
+    pub enabled: bool,
+}
+
+const BUILTIN_FILES: &[&str] = &[
+    include_str!("../../blocklists/social.json"),
+    include_str!("../../blocklists/video.json"),
+    include_str!("../../blocklists/news.json"),
+    include_str!("../../blocklists/shopping.json"),
+    include_str!("../../blocklists/gaming.json"),
+];
+
+static BUILTIN: Lazy<Vec<Category>> = Lazy::new(|| {
+    BUILTIN_FILES
+        .iter()
+        .map(|content| serde_json::from_str(content).expect("invalid built-in blocklist"))
+        .collect()
+});
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct CategoryState {
+    /// Enabled category ids and the list version last applied.
+    enabled: BTreeMap<String, u32>,
+    /// Rules that were added by categories, as opposed to custom entries.
+    owned: BTreeSet<String>,
+}
+
+fn get_state_path() -> PathBuf {
+    let path = dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("FocusKeeper")
+        .join("categories.json");
+
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    path
+}
+
+fn load_state() -> CategoryState {
+    fs::read_to_string(get_state_path())
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save_state(state: &CategoryState) -> Result<(), BlockerError> {
+    let content = serde_json::to_string_pretty(state)
+        .map_err(|e| BlockerError::Config(e.into()))?;
+    fs::write(get_state_path(), content).map_err(BlockerError::Config)
+}
+
+pub fn find_category(id: &str) -> Option<&'static Category> {
+    BUILTIN.iter().find(|c| c.id == id)
+}
+
+pub fn get_categories() -> Vec<CategoryInfo> {
+    let state = load_state();
+    BUILTIN
+        .iter()
+        .map(|c| CategoryInfo {
+            id: c.id.clone(),
+            name: c.name.clone(),
+            version: c.version,
+            domains: c.domains.clone(),
+            enabled: state.enabled.contains_key(&c.id),
+        })
+        .collect()
+}
+
+pub fn enabled_categories() -> Vec<String> {
+    load_state().enabled.into_keys().collect()
+}
+
+// 让屏蔽列表等于“已启用分类的并集 + 自定义条目”：只增删分类自己加进去的规则
+fn apply(blocker: &dyn WebsiteBlocker, mut state: CategoryState) -> Result<(), BlockerError> {
+    let desired: BTreeSet<String> = state
+        .enabled
+        .keys()
+        .filter_map(|id| find_category(id))
+        .flat_map(|c| c.domains.iter())
+        .filter_map(|d| DomainRule::parse(d))
+        .map(|rule| rule.to_string())
+        .collect();
+    let current: BTreeSet<String> = blocker.list()?.into_iter().collect();
+
+    let to_add: Vec<String> = desired.difference(&current).cloned().collect();
+    let to_remove: Vec<String> = state.owned.difference(&desired).cloned().collect();
+
+    blocker.block(&to_add)?;
+    for rule in &to_remove {
+        blocker.unblock(rule)?;
+    }
+
+    state.owned = state.owned.intersection(&desired).cloned().collect();
+    state.owned.extend(to_add);
+    // 分类文件在此期间被删除时不再保留其启用状态
+    state.enabled.retain(|id, _| find_category(id).is_some());
+    for (id, version) in state.enabled.iter_mut() {
+        if let Some(category) = find_category(id) {
+            *version = category.version;
+        }
+    }
+    save_state(&state)
+}
+
+/// Rules currently on the block list only because an enabled category added them.
+pub fn owned_rules() -> BTreeSet<String> {
+    load_state().owned
+}
+
+/// Saved category state, for undoing a failed bulk change.
+pub struct CategorySnapshot(CategoryState);
+
+pub fn snapshot() -> CategorySnapshot {
+    CategorySnapshot(load_state())
+}
+
+/// Writes back a snapshot. The block list itself is not touched.
+pub fn restore(snapshot: CategorySnapshot) -> Result<(), BlockerError> {
+    save_state(&snapshot.0)
+}
+
+pub fn enable_category(blocker: &dyn WebsiteBlocker, id: &str) -> Result<(), BlockerError> {
+    let category = find_category(id).ok_or_else(|| BlockerError::UnknownCategory(id.to_string()))?;
+    let mut state = load_state();
+    state.enabled.insert(category.id.clone(), category.version);
+    apply(blocker, state)
+}
+
+pub fn disable_category(blocker: &dyn WebsiteBlocker, id: &str) -> Result<(), BlockerError> {
+    let mut state = load_state();
+    if state.enabled.remove(id).is_none() {
+        return Err(BlockerError::UnknownCategory(id.to_string()));
+    }
+    apply(blocker, state)
+}
+
+/// Re-applies enabled categories whose shipped list is newer than the applied one.
+pub fn sync_categories(blocker: &dyn WebsiteBlocker) -> Result<(), BlockerError> {
+    let state = load_state();
+    let outdated = state.enabled.iter().any(|(id, version)| {
+        find_category(id).map(|c| c.version != *version).unwrap_or(true)
+    });
+    if outdated {
+        apply(blocker, state)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,942 @@
+use super::rules::{DomainRule, RuleKind};
+use super::website::{BlockerBackend, BlockerError, WebsiteBlocker};
+use once_cell::sync::{Lazy, OnceCell};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, UdpSocket};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex, RwLock, Weak};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use thiserror::Error;
+
+/// Address the local resolver listens on; the system DNS is pointed here while it runs.
+pub const LISTEN_ADDR: &str = "127.0.0.1:53";
+/// IPv6 counterpart of `LISTEN_ADDR`, so IPv6 DNS settings can be redirected too.
+const LISTEN_ADDR_V6: &str = "[::1]:53";
+const FALLBACK_UPSTREAM: &str = "1.1.1.1:53";
+const UPSTREAM_TIMEOUT: Duration = Duration::from_secs(3);
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+// 转发上游查询的线程数和排队上限，超出时直接回 SERVFAIL
+const FORWARD_WORKERS: usize = 8;
+const FORWARD_QUEUE: usize = 64;
+const MAX_PACKET: usize = 4096;
+const SINKHOLE_TTL: u32 = 60;
+
+const RCODE_NOERROR: u8 = 0;
+const RCODE_SERVFAIL: u8 = 2;
+const RCODE_NXDOMAIN: u8 = 3;
+const QTYPE_A: u16 = 1;
+const QTYPE_AAAA: u16 = 28;
+
+#[derive(Error, Debug)]
+pub enum DnsError {
+    #[error("Failed to bind local resolver on {LISTEN_ADDR}: {0}")]
+    Bind(std::io::Error),
+    #[error("Failed to change system DNS settings: {0}")]
+    SystemDns(String),
+    #[error("Failed to save DNS backup: {0}")]
+    Backup(std::io::Error),
+    #[error("Failed to save resolver config: {0}")]
+    Config(std::io::Error),
+    #[error("Invalid upstream DNS server: {0}")]
+    InvalidUpstream(String),
+}
+
+/// How blocked names are answered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BlockResponse {
+    #[default]
+    Nxdomain,
+    /// Answer A/AAAA queries with fixed addresses, e.g. a local "blocked" page.
+    Sinkhole { ipv4: Ipv4Addr, ipv6: Ipv6Addr },
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ResolverConfig {
+    /// Upstream server as `ip` or `ip:port`; the system's original DNS when unset.
+    pub upstream: Option<String>,
+    pub response: BlockResponse,
+    /// Rules enforced when the DNS backend is the active website blocker.
+    pub blocked: Vec<DomainRule>,
+}
+
+struct Resolver {
+    stop: Arc<AtomicBool>,
+    /// Upstream taken from the system settings replaced at start.
+    system_upstream: SocketAddr,
+    /// Listener threads; joined on stop so the port is free again afterwards.
+    listeners: Vec<JoinHandle<()>>,
+}
+
+struct ForwardJob {
+    query: Vec<u8>,
+    question_end: usize,
+    client: SocketAddr,
+    upstream: SocketAddr,
+    // 只持有弱引用，监听线程退出后套接字立即关闭，不会被排队中的转发任务拖住
+    socket: Weak<UdpSocket>,
+}
+
+static RESOLVER: Lazy<Mutex<Option<Resolver>>> = Lazy::new(|| Mutex::new(None));
+static CONFIG: Lazy<RwLock<ResolverConfig>> = Lazy::new(|| RwLock::new(load_config()));
+// 被拦截的查询通知给上层用于统计；在解析线程里调用，实现需要足够快
+static BLOCK_OBSERVER: OnceCell<fn(&str)> = OnceCell::new();
+// 允许名单模式只在当前会话内有效，不持久化
+static ALLOW_ONLY: Lazy<RwLock<Option<Vec<DomainRule>>>> = Lazy::new(|| RwLock::new(None));
+
+fn lock_resolver() -> std::sync::MutexGuard<'static, Option<Resolver>> {
+    RESOLVER.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn read_config() -> std::sync::RwLockReadGuard<'static, ResolverConfig> {
+    CONFIG.read().unwrap_or_else(|e| e.into_inner())
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResolverStatus {
+    pub running: bool,
+    pub allowed: Vec<String>,
+    pub blocked: Vec<String>,
+    pub upstream: Option<String>,
+}
+
+/// System DNS settings captured before switching to the local resolver.
+/// Persisted so a crash doesn't leave the machine pointing at a dead resolver.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct DnsBackup {
+    /// Original /etc/resolv.conf content (Linux).
+    #[serde(default)]
+    resolv_conf: Option<String>,
+    /// Original symlink target when /etc/resolv.conf was managed by e.g. systemd-resolved.
+    #[serde(default)]
+    resolv_link: Option<PathBuf>,
+    /// Per network service (macOS) or interface index (Windows) server lists.
+    /// On Windows only statically configured servers are listed.
+    #[serde(default)]
+    interfaces: Vec<(String, Vec<String>)>,
+    /// Windows interface indexes that got their DNS servers from DHCP.
+    #[serde(default)]
+    dhcp_interfaces: Vec<String>,
+}
+
+fn get_data_path(file: &str) -> PathBuf {
+    let path = dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("FocusKeeper")
+        .join(file);
+
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    path
+}
+
+fn get_backup_path() -> PathBuf {
+    get_data_path("dns-backup.json")
+}
+
+fn load_backup() -> Option<DnsBackup> {
+    let content = fs::read_to_string(get_backup_path()).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+fn save_backup(backup: &DnsBackup) -> Result<(), DnsError> {
+    let content = serde_json::to_string_pretty(backup)
+        .map_err(|e| DnsError::Backup(e.into()))?;
+    fs::write(get_backup_path(), content).map_err(DnsError::Backup)
+}
+
+fn load_config() -> ResolverConfig {
+    fs::read_to_string(get_data_path("dns-config.json"))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save_config(config: &ResolverConfig) -> Result<(), DnsError> {
+    let content = serde_json::to_string_pretty(config)
+        .map_err(|e| DnsError::Config(e.into()))?;
+    fs::write(get_data_path("dns-config.json"), content).map_err(DnsError::Config)
+}
+
+fn parse_upstream(upstream: &str) -> Option<SocketAddr> {
+    let upstream = upstream.trim();
+    upstream
+        .parse::<SocketAddr>()
+        .ok()
+        .or_else(|| upstream.parse::<IpAddr>().ok().map(|ip| SocketAddr::new(ip, 53)))
+}
+
+pub fn is_valid_upstream(upstream: &str) -> bool {
+    parse_upstream(upstream)
+        .map(|addr| is_usable_upstream(&addr.ip()))
+        .unwrap_or(false)
+}
+
+pub fn get_resolver_config() -> ResolverConfig {
+    read_config().clone()
+}
+
+/// Replaces the resolver config, persisting it and starting or stopping the
+/// resolver as needed.
+pub fn set_resolver_config(config: ResolverConfig) -> Result<(), DnsError> {
+    if let Some(upstream) = &config.upstream {
+        if !is_valid_upstream(upstream) {
+            return Err(DnsError::InvalidUpstream(upstream.clone()));
+        }
+    }
+    save_config(&config)?;
+    *CONFIG.write().unwrap_or_else(|e| e.into_inner()) = config;
+    sync_resolver()
+}
+
+fn update_blocked<F: FnOnce(&mut Vec<DomainRule>)>(f: F) -> Result<(), DnsError> {
+    let mut config = get_resolver_config();
+    f(&mut config.blocked);
+    set_resolver_config(config)
+}
+
+/// Restricts lookups to `domains` and their subdomains, or lifts the restriction with `None`.
+pub fn set_allow_only(domains: Option<&[String]>) -> Result<(), DnsError> {
+    let rules = domains.map(|domains| {
+        domains
+            .iter()
+            .filter_map(|d| DomainRule::parse(d))
+            .map(|rule| DomainRule {
+                // 允许名单里写 example.com 时，子域名一并放行
+                kind: if rule.kind == RuleKind::Exact { RuleKind::Suffix } else { rule.kind },
+                domain: rule.domain,
+            })
+            .collect()
+    });
+    *ALLOW_ONLY.write().unwrap_or_else(|e| e.into_inner()) = rules;
+    sync_resolver()
+}
+
+fn is_blocked(name: &str) -> bool {
+    if let Some(allowed) = ALLOW_ONLY.read().unwrap_or_else(|e| e.into_inner()).as_ref() {
+        if !allowed.iter().any(|rule| rule.matches(name)) {
+            return true;
+        }
+    }
+    read_config().blocked.iter().any(|rule| rule.matches(name))
+}
+
+/// Registers a callback run for every query the local resolver blocks. Only the first call takes effect.
+pub fn set_block_observer(observer: fn(&str)) {
+    let _ = BLOCK_OBSERVER.set(observer);
+}
+
+fn is_needed() -> bool {
+    ALLOW_ONLY.read().unwrap_or_else(|e| e.into_inner()).is_some() || !read_config().blocked.is_empty()
+}
+
+/// Starts the resolver when there is something to enforce and stops it otherwise.
+pub fn sync_resolver() -> Result<(), DnsError> {
+    let running = lock_resolver().is_some();
+    match (is_needed(), running) {
+        (true, false) => start_resolver(),
+        (false, true) => stop_resolver(),
+        _ => Ok(()),
+    }
+}
+
+/// Starts the resolver and points the system DNS at it.
+fn start_resolver() -> Result<(), DnsError> {
+    let mut resolver = lock_resolver();
+    if resolver.is_some() {
+        return Ok(());
+    }
+
+    // 先绑定端口、启动转发线程，失败时系统 DNS 未改动，也不会留下备份
+    let mut sockets = vec![bind_listener(LISTEN_ADDR)?];
+    match bind_listener(LISTEN_ADDR_V6) {
+        Ok(socket) => sockets.push(socket),
+        // 系统未启用 IPv6 时不存在 IPv6 DNS，只监听 IPv4 即可
+        Err(e) => log::warn!("local resolver not listening on {}: {}", LISTEN_ADDR_V6, e),
+    }
+    let ipv6 = sockets.len() > 1;
+    let jobs = spawn_forward_pool().map_err(DnsError::Bind)?;
+
+    // 上次异常退出时备份仍在，说明当前系统 DNS 已指向本地，不能覆盖真正的原始配置
+    let (backup, fresh_backup) = match load_backup() {
+        Some(backup) => (backup, false),
+        None => {
+            let backup = capture_system_dns()?;
+            save_backup(&backup)?;
+            (backup, true)
+        }
+    };
+    let system_upstream = upstream_from_backup(&backup);
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let mut listeners = Vec::new();
+    for socket in sockets {
+        let listener_stop = stop.clone();
+        let jobs = jobs.clone();
+        let spawned = thread::Builder::new()
+            .name("dns-resolver".to_string())
+            .spawn(move || serve(socket, system_upstream, listener_stop, jobs));
+        match spawned {
+            Ok(handle) => listeners.push(handle),
+            Err(e) => {
+                join_listeners(&stop, listeners);
+                // 系统 DNS 尚未改动，刚写下的备份没有用处；沿用的旧备份仍需保留以便恢复
+                if fresh_backup {
+                    let _ = fs::remove_file(get_backup_path());
+                }
+                return Err(DnsError::Bind(e));
+            }
+        }
+    }
+
+    if let Err(e) = apply_system_dns(ipv6) {
+        join_listeners(&stop, listeners);
+        let _ = restore_system_dns(&backup);
+        let _ = fs::remove_file(get_backup_path());
+        return Err(e);
+    }
+
+    *resolver = Some(Resolver {
+        stop,
+        system_upstream,
+        listeners,
+    });
+    log::info!("local resolver started on {}", LISTEN_ADDR);
+    Ok(())
+}
+
+fn bind_listener(addr: &str) -> Result<UdpSocket, DnsError> {
+    let socket = UdpSocket::bind(addr).map_err(DnsError::Bind)?;
+    socket
+        .set_read_timeout(Some(POLL_INTERVAL))
+        .map_err(DnsError::Bind)?;
+    Ok(socket)
+}
+
+// 通知监听线程退出并等待其结束，返回后端口已释放，可以立即重新绑定
+fn join_listeners(stop: &AtomicBool, listeners: Vec<JoinHandle<()>>) {
+    stop.store(true, Ordering::SeqCst);
+    for listener in listeners {
+        let _ = listener.join();
+    }
+}
+
+/// Stops the resolver and restores the system DNS settings.
+fn stop_resolver() -> Result<(), DnsError> {
+    let resolver = lock_resolver().take();
+    if let Some(resolver) = resolver {
+        join_listeners(&resolver.stop, resolver.listeners);
+    }
+    recover_system_dns()
+}
+
+/// Restores DNS settings left behind by a previous run; a no-op when no backup exists.
+pub fn recover_system_dns() -> Result<(), DnsError> {
+    if lock_resolver().is_some() {
+        return Ok(());
+    }
+    if let Some(backup) = load_backup() {
+        restore_system_dns(&backup)?;
+        let _ = fs::remove_file(get_backup_path());
+    }
+    Ok(())
+}
+
+fn current_upstream(system_upstream: SocketAddr) -> SocketAddr {
+    read_config()
+        .upstream
+        .as_deref()
+        .and_then(parse_upstream)
+        .unwrap_or(system_upstream)
+}
+
+pub fn resolver_status() -> ResolverStatus {
+    let resolver = lock_resolver();
+    let allowed = ALLOW_ONLY
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .iter()
+        .flatten()
+        .map(|rule| rule.to_string())
+        .collect();
+
+    ResolverStatus {
+        running: resolver.is_some(),
+        allowed,
+        blocked: read_config().blocked.iter().map(|rule| rule.to_string()).collect(),
+        upstream: resolver
+            .as_ref()
+            .map(|r| current_upstream(r.system_upstream).to_string()),
+    }
+}
+
+fn serve(socket: UdpSocket, system_upstream: SocketAddr, stop: Arc<AtomicBool>, jobs: SyncSender<ForwardJob>) {
+    let socket = Arc::new(socket);
+    let mut buf = [0u8; MAX_PACKET];
+
+    while !stop.load(Ordering::SeqCst) {
+        let (len, client) = match socket.recv_from(&mut buf) {
+            Ok(received) => received,
+            Err(_) => continue,
+        };
+        let query = buf[..len].to_vec();
+        let Some(question) = parse_question(&query) else {
+            continue;
+        };
+
+        if is_blocked(&question.name) {
+            log::debug!("dns: blocked {}", question.name);
+            if let Some(observer) = BLOCK_OBSERVER.get() {
+                observer(&question.name);
+            }
+            let response = match read_config().response {
+                BlockResponse::Nxdomain => empty_response(&query, question.end, RCODE_NXDOMAIN),
+                BlockResponse::Sinkhole { ipv4, ipv6 } => sinkhole_response(&query, &question, ipv4, ipv6),
+            };
+            let _ = socket.send_to(&response, client);
+            continue;
+        }
+
+        // 上游查询交给固定大小的线程池，避免一个慢查询阻塞其他请求，也不会无限制地创建线程
+        let job = ForwardJob {
+            query,
+            question_end: question.end,
+            client,
+            upstream: current_upstream(system_upstream),
+            socket: Arc::downgrade(&socket),
+        };
+        if let Err(TrySendError::Full(job) | TrySendError::Disconnected(job)) = jobs.try_send(job) {
+            let _ = socket.send_to(&empty_response(&job.query, job.question_end, RCODE_SERVFAIL), client);
+        }
+    }
+}
+
+/// Starts the upstream forwarding threads. They exit once every listener has
+/// dropped its sender.
+fn spawn_forward_pool() -> std::io::Result<SyncSender<ForwardJob>> {
+    let (sender, receiver) = mpsc::sync_channel::<ForwardJob>(FORWARD_QUEUE);
+    let receiver = Arc::new(Mutex::new(receiver));
+    for index in 0..FORWARD_WORKERS {
+        let receiver = receiver.clone();
+        thread::Builder::new()
+            .name(format!("dns-forward-{}", index))
+            .spawn(move || forward_worker(&receiver))?;
+    }
+    Ok(sender)
+}
+
+fn forward_worker(receiver: &Mutex<Receiver<ForwardJob>>) {
+    loop {
+        let job = match receiver.lock().unwrap_or_else(|e| e.into_inner()).recv() {
+            Ok(job) => job,
+            Err(_) => return,
+        };
+        let response = forward(&job.query, job.upstream)
+            .unwrap_or_else(|| empty_response(&job.query, job.question_end, RCODE_SERVFAIL));
+        if let Some(socket) = job.socket.upgrade() {
+            let _ = socket.send_to(&response, job.client);
+        }
+    }
+}
+
+fn forward(query: &[u8], upstream: SocketAddr) -> Option<Vec<u8>> {
+    let bind_addr = if upstream.is_ipv4() { "0.0.0.0:0" } else { "[::]:0" };
+    let socket = UdpSocket::bind(bind_addr).ok()?;
+    socket.set_read_timeout(Some(UPSTREAM_TIMEOUT)).ok()?;
+    socket.send_to(query, upstream).ok()?;
+
+    let mut buf = [0u8; MAX_PACKET];
+    loop {
+        let (len, from) = socket.recv_from(&mut buf).ok()?;
+        // 只接受来自上游且事务 ID 一致的应答
+        if from == upstream && len >= 2 && buf[..2] == query[..2] {
+            return Some(buf[..len].to_vec());
+        }
+    }
+}
+
+struct Question {
+    name: String,
+    qtype: u16,
+    /// Offset just past the question section.
+    end: usize,
+}
+
+fn parse_question(packet: &[u8]) -> Option<Question> {
+    if packet.len() < 12 || packet[2] & 0x80 != 0 {
+        return None;
+    }
+    let qdcount = u16::from_be_bytes([packet[4], packet[5]]);
+    if qdcount == 0 {
+        return None;
+    }
+
+    let mut labels = Vec::new();
+    let mut pos = 12;
+    loop {
+        let len = *packet.get(pos)? as usize;
+        pos += 1;
+        if len == 0 {
+            break;
+        }
+        // 问题段不应使用压缩指针
+        if len & 0xC0 != 0 {
+            return None;
+        }
+        let label = packet.get(pos..pos + len)?;
+        labels.push(String::from_utf8_lossy(label).to_string());
+        pos += len;
+    }
+
+    let qtype = u16::from_be_bytes([*packet.get(pos)?, *packet.get(pos + 1)?]);
+    let end = pos + 4;
+    if end > packet.len() {
+        return None;
+    }
+    Some(Question {
+        name: labels.join("."),
+        qtype,
+        end,
+    })
+}
+
+fn empty_response(query: &[u8], question_end: usize, rcode: u8) -> Vec<u8> {
+    let mut response = query[..question_end].to_vec();
+    // QR=1，保留 opcode 和 RD；RA=1
+    response[2] = 0x80 | (query[2] & 0x79);
+    response[3] = 0x80 | rcode;
+    response[4..6].copy_from_slice(&1u16.to_be_bytes());
+    response[6..12].fill(0);
+    response
+}
+
+fn sinkhole_response(query: &[u8], question: &Question, ipv4: Ipv4Addr, ipv6: Ipv6Addr) -> Vec<u8> {
+    let mut response = empty_response(query, question.end, RCODE_NOERROR);
+    let rdata = match question.qtype {
+        QTYPE_A => ipv4.octets().to_vec(),
+        QTYPE_AAAA => ipv6.octets().to_vec(),
+        // 其他类型返回无记录的 NOERROR
+        _ => return response,
+    };
+
+    response[6..8].copy_from_slice(&1u16.to_be_bytes());
+    // 名称使用指向问题段（偏移 12）的压缩指针
+    response.extend_from_slice(&[0xC0, 0x0C]);
+    response.extend_from_slice(&question.qtype.to_be_bytes());
+    response.extend_from_slice(&1u16.to_be_bytes());
+    response.extend_from_slice(&SINKHOLE_TTL.to_be_bytes());
+    response.extend_from_slice(&(rdata.len() as u16).to_be_bytes());
+    response.extend_from_slice(&rdata);
+    response
+}
+
+fn is_usable_upstream(ip: &IpAddr) -> bool {
+    !ip.is_unspecified() && *ip != IpAddr::from([127, 0, 0, 1]) && *ip != IpAddr::from(Ipv6Addr::LOCALHOST)
+}
+
+fn upstream_from_backup(backup: &DnsBackup) -> SocketAddr {
+    let mut candidates: Vec<String> = Vec::new();
+    if let Some(content) = &backup.resolv_conf {
+        candidates.extend(
+            content
+                .lines()
+                .filter_map(|line| line.trim().strip_prefix("nameserver"))
+                .map(|ip| ip.trim().to_string()),
+        );
+    }
+    for (_, servers) in &backup.interfaces {
+        candidates.extend(servers.iter().cloned());
+    }
+
+    candidates
+        .iter()
+        .filter_map(|ip| ip.parse::<IpAddr>().ok())
+        .find(is_usable_upstream)
+        .map(|ip| SocketAddr::new(ip, 53))
+        .unwrap_or_else(|| FALLBACK_UPSTREAM.parse().unwrap())
+}
+
+fn parse_rules(domains: &[String]) -> Result<Vec<DomainRule>, BlockerError> {
+    domains
+        .iter()
+        .map(|d| DomainRule::parse(d).ok_or_else(|| BlockerError::InvalidRule(d.clone())))
+        .collect()
+}
+
+/// Website blocking through the local resolver; supports wildcard and suffix rules
+/// and isn't affected by applications that ignore the hosts file.
+pub struct DnsBlocker;
+
+impl WebsiteBlocker for DnsBlocker {
+    fn backend(&self) -> BlockerBackend {
+        BlockerBackend::Dns
+    }
+
+    fn list(&self) -> Result<Vec<String>, BlockerError> {
+        Ok(read_config().blocked.iter().map(|rule| rule.to_string()).collect())
+    }
+
+    fn block(&self, domains: &[String]) -> Result<(), BlockerError> {
+        let rules = parse_rules(domains)?;
+        update_blocked(|blocked| {
+            for rule in rules {
+                if !blocked.contains(&rule) {
+                    blocked.push(rule);
+                }
+            }
+        })?;
+        Ok(())
+    }
+
+    fn unblock(&self, domain: &str) -> Result<(), BlockerError> {
+        let rule = DomainRule::parse(domain).ok_or_else(|| BlockerError::InvalidRule(domain.to_string()))?;
+        update_blocked(|blocked| blocked.retain(|r| *r != rule))?;
+        Ok(())
+    }
+
+    fn clear(&self) -> Result<(), BlockerError> {
+        update_blocked(|blocked| blocked.clear())?;
+        Ok(())
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn run(program: &str, args: &[&str]) -> Result<String, DnsError> {
+    let output = std::process::Command::new(program)
+        .args(args)
+        .output()
+        .map_err(|e| DnsError::SystemDns(format!("{}: {}", program, e)))?;
+    if !output.status.success() {
+        return Err(DnsError::SystemDns(format!(
+            "{} failed: {}",
+            program,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+#[cfg(target_os = "linux")]
+const RESOLV_CONF: &str = "/etc/resolv.conf";
+
+#[cfg(target_os = "linux")]
+fn capture_system_dns() -> Result<DnsBackup, DnsError> {
+    let path = std::path::Path::new(RESOLV_CONF);
+    Ok(DnsBackup {
+        resolv_conf: fs::read_to_string(path).ok(),
+        resolv_link: fs::read_link(path).ok(),
+        interfaces: Vec::new(),
+        dhcp_interfaces: Vec::new(),
+    })
+}
+
+#[cfg(target_os = "linux")]
+fn apply_system_dns(_ipv6: bool) -> Result<(), DnsError> {
+    // resolv.conf 可能是指向 systemd-resolved 的符号链接，先移除再写入普通文件
+    if fs::symlink_metadata(RESOLV_CONF).map(|m| m.file_type().is_symlink()).unwrap_or(false) {
+        fs::remove_file(RESOLV_CONF).map_err(|e| DnsError::SystemDns(e.to_string()))?;
+    }
+    fs::write(RESOLV_CONF, "# Focus Keeper local resolver\nnameserver 127.0.0.1\n")
+        .map_err(|e| DnsError::SystemDns(e.to_string()))
+}
+
+#[cfg(target_os = "linux")]
+fn restore_system_dns(backup: &DnsBackup) -> Result<(), DnsError> {
+    let map_err = |e: std::io::Error| DnsError::SystemDns(e.to_string());
+    if let Some(target) = &backup.resolv_link {
+        let _ = fs::remove_file(RESOLV_CONF);
+        std::os::unix::fs::symlink(target, RESOLV_CONF).map_err(map_err)?;
+    } else if let Some(content) = &backup.resolv_conf {
+        fs::write(RESOLV_CONF, content).map_err(map_err)?;
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+fn network_services() -> Result<Vec<String>, DnsError> {
+    let output = run("networksetup", &["-listallnetworkservices"])?;
+    // 第一行是说明文字，带 * 前缀的服务已被禁用
+    Ok(output
+        .lines()
+        .skip(1)
+        .filter(|line| !line.trim().is_empty() && !line.starts_with('*'))
+        .map(|line| line.trim().to_string())
+        .collect())
+}
+
+#[cfg(target_os = "macos")]
+fn capture_system_dns() -> Result<DnsBackup, DnsError> {
+    let mut interfaces = Vec::new();
+    for service in network_services()? {
+        let output = run("networksetup", &["-getdnsservers", &service])?;
+        let servers = output
+            .lines()
+            .map(|line| line.trim())
+            .filter(|line| line.parse::<IpAddr>().is_ok())
+            .map(|line| line.to_string())
+            .collect();
+        interfaces.push((service, servers));
+    }
+
+    Ok(DnsBackup {
+        // DHCP 下发的 DNS 不会出现在 networksetup 中，从生成的 resolv.conf 取上游
+        resolv_conf: fs::read_to_string("/etc/resolv.conf").ok(),
+        resolv_link: None,
+        interfaces,
+        dhcp_interfaces: Vec::new(),
+    })
+}
+
+#[cfg(target_os = "macos")]
+fn apply_system_dns(_ipv6: bool) -> Result<(), DnsError> {
+    for service in network_services()? {
+        run("networksetup", &["-setdnsservers", &service, "127.0.0.1"])?;
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+fn restore_system_dns(backup: &DnsBackup) -> Result<(), DnsError> {
+    for (service, servers) in &backup.interfaces {
+        let mut args = vec!["-setdnsservers", service.as_str()];
+        if servers.is_empty() {
+            args.push("Empty");
+        } else {
+            args.extend(servers.iter().map(|s| s.as_str()));
+        }
+        run("networksetup", &args)?;
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "windows")]
+fn capture_system_dns() -> Result<DnsBackup, DnsError> {
+    // 注册表中接口的 NameServer 为空说明 DNS 由 DHCP 下发，恢复时要重置而不是写成静态地址
+    let output = run(
+        "powershell",
+        &[
+            "-NoProfile",
+            "-Command",
+            "Get-DnsClientServerAddress | Where-Object { $_.ServerAddresses.Count -gt 0 } | ForEach-Object { \
+               $stack = if ($_.AddressFamily -eq 2) { 'Tcpip' } else { 'Tcpip6' }; \
+               $guid = (Get-NetAdapter -InterfaceIndex $_.InterfaceIndex -ErrorAction SilentlyContinue).InterfaceGuid; \
+               $static = if ($guid) { (Get-ItemProperty \"HKLM:\\SYSTEM\\CurrentControlSet\\Services\\$stack\\Parameters\\Interfaces\\$guid\" -ErrorAction SilentlyContinue).NameServer }; \
+               $source = if ($static) { 'static' } else { 'dhcp' }; \
+               \"$($_.InterfaceIndex)|$source|$($_.ServerAddresses -join ',')\" }",
+        ],
+    )?;
+
+    let mut interfaces: Vec<(String, Vec<String>)> = Vec::new();
+    let mut dhcp_interfaces: Vec<String> = Vec::new();
+    for line in output.lines() {
+        let mut fields = line.trim().splitn(3, '|');
+        let (Some(index), Some(source), Some(servers)) = (fields.next(), fields.next(), fields.next()) else {
+            continue;
+        };
+        let position = match interfaces.iter().position(|(i, _)| i == index) {
+            Some(position) => position,
+            None => {
+                interfaces.push((index.to_string(), Vec::new()));
+                interfaces.len() - 1
+            }
+        };
+        if source == "dhcp" {
+            if !dhcp_interfaces.iter().any(|i| i == index) {
+                dhcp_interfaces.push(index.to_string());
+            }
+        } else {
+            interfaces[position]
+                .1
+                .extend(servers.split(',').map(|s| s.trim().to_string()).filter(|s| !s.is_empty()));
+        }
+    }
+
+    Ok(DnsBackup {
+        resolv_conf: None,
+        resolv_link: None,
+        interfaces,
+        dhcp_interfaces,
+    })
+}
+
+#[cfg(target_os = "windows")]
+fn apply_system_dns(ipv6: bool) -> Result<(), DnsError> {
+    // IPv4 和 IPv6 的 DNS 都要指向本地，否则系统会绕过本地解析器走 IPv6 DNS
+    let servers = if ipv6 { "'127.0.0.1','::1'" } else { "'127.0.0.1'" };
+    let script = format!(
+        "Get-DnsClientServerAddress | Where-Object {{ $_.ServerAddresses.Count -gt 0 }} | \
+         Select-Object -ExpandProperty InterfaceIndex -Unique | \
+         ForEach-Object {{ Set-DnsClientServerAddress -InterfaceIndex $_ -ServerAddresses {} }}",
+        servers
+    );
+    run("powershell", &["-NoProfile", "-Command", &script])?;
+    Ok(())
+}
+
+#[cfg(target_os = "windows")]
+fn restore_system_dns(backup: &DnsBackup) -> Result<(), DnsError> {
+    for (index, servers) in &backup.interfaces {
+        // 先整体重置为 DHCP，再写回原本静态配置的地址族，DHCP 下发的一侧保持自动获取
+        let mut script = String::new();
+        if backup.dhcp_interfaces.contains(index) {
+            script.push_str(&format!(
+                "Set-DnsClientServerAddress -InterfaceIndex {} -ResetServerAddresses; ",
+                index
+            ));
+        }
+        if !servers.is_empty() {
+            let addresses = servers
+                .iter()
+                .map(|s| format!("'{}'", s))
+                .collect::<Vec<_>>()
+                .join(",");
+            script.push_str(&format!(
+                "Set-DnsClientServerAddress -InterfaceIndex {} -ServerAddresses {}",
+                index, addresses
+            ));
+        }
+        if !script.is_empty() {
+            run("powershell", &["-NoProfile", "-Command", &script])?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 事务 ID 0x1234，RD=1，一个问题
+    const HEADER: [u8; 12] = [0x12, 0x34, 0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 0];
+
+    fn query(name: &str, qtype: u16) -> Vec<u8> {
+        let mut packet = HEADER.to_vec();
+        for label in name.split('.') {
+            packet.push(label.len() as u8);
+            packet.extend_from_slice(label.as_bytes());
+        }
+        packet.push(0);
+        packet.extend_from_slice(&qtype.to_be_bytes());
+        packet.extend_from_slice(&1u16.to_be_bytes());
+        packet
+    }
+
+    fn with_name(name: &[u8]) -> Vec<u8> {
+        let mut packet = HEADER.to_vec();
+        packet.extend_from_slice(name);
+        packet.extend_from_slice(&[0, 1, 0, 1]);
+        packet
+    }
+
+    #[test]
+    fn parses_a_well_formed_question() {
+        let packet = query("Ads.Example.com", QTYPE_AAAA);
+        let question = parse_question(&packet).unwrap();
+        assert_eq!(question.name, "Ads.Example.com");
+        assert_eq!(question.qtype, QTYPE_AAAA);
+        assert_eq!(question.end, packet.len());
+    }
+
+    #[test]
+    fn rejects_truncated_packets() {
+        let packet = query("ads.example.com", QTYPE_A);
+        for len in 0..packet.len() {
+            assert!(parse_question(&packet[..len]).is_none(), "accepted {} of {} bytes", len, packet.len());
+        }
+
+        let mut response = packet.clone();
+        response[2] |= 0x80;
+        assert!(parse_question(&response).is_none());
+        let mut no_question = packet;
+        no_question[5] = 0;
+        assert!(parse_question(&no_question).is_none());
+    }
+
+    #[test]
+    fn rejects_compression_pointers_and_oversized_labels() {
+        // 指向自身的压缩指针
+        assert!(parse_question(&with_name(&[0xC0, 0x0C])).is_none());
+        assert!(parse_question(&with_name(&[3, b'a', b'd', b's', 0xC0, 0x0C])).is_none());
+        // 长度字节 64 及以上属于保留或指针形式
+        let mut oversized = vec![64];
+        oversized.extend_from_slice(&[b'a'; 64]);
+        oversized.push(0);
+        assert!(parse_question(&with_name(&oversized)).is_none());
+        assert!(parse_question(&with_name(&[0x80, b'a', 0])).is_none());
+        // 声明的长度超出数据包
+        assert!(parse_question(&with_name(&[63, b'a', b'b'])).is_none());
+
+        let longest = "a".repeat(63);
+        let question = parse_question(&query(&format!("{}.com", longest), QTYPE_A)).unwrap();
+        assert_eq!(question.name, format!("{}.com", longest));
+    }
+
+    #[test]
+    fn empty_response_echoes_only_the_question() {
+        let mut packet = query("ads.example.com", QTYPE_A);
+        let question_end = packet.len();
+        // 附加段里的 EDNS OPT 记录不应带入应答
+        packet[11] = 1;
+        packet.extend_from_slice(&[0, 0, 41, 0x10, 0, 0, 0, 0, 0, 0, 0]);
+        let question = parse_question(&packet).unwrap();
+        assert_eq!(question.end, question_end);
+
+        let response = empty_response(&packet, question.end, RCODE_NXDOMAIN);
+        assert_eq!(response.len(), question_end);
+        assert_eq!(response[..2], [0x12, 0x34]);
+        assert_eq!(response[2..4], [0x81, 0x80 | RCODE_NXDOMAIN]);
+        assert_eq!(response[4..12], [0, 1, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(response[12..], packet[12..question_end]);
+    }
+
+    #[test]
+    fn sinkhole_response_answers_a_and_aaaa_only() {
+        let ipv4 = Ipv4Addr::new(0, 0, 0, 0);
+        let ipv6 = Ipv6Addr::UNSPECIFIED;
+
+        for (qtype, rdata) in [(QTYPE_A, ipv4.octets().to_vec()), (QTYPE_AAAA, ipv6.octets().to_vec())] {
+            let packet = query("ads.example.com", qtype);
+            let question = parse_question(&packet).unwrap();
+            let response = sinkhole_response(&packet, &question, ipv4, ipv6);
+            assert_eq!(response[6..8], [0, 1]);
+            let answer = &response[question.end..];
+            assert_eq!(answer[..4], [0xC0, 0x0C, (qtype >> 8) as u8, qtype as u8]);
+            assert_eq!(answer[10..12], (rdata.len() as u16).to_be_bytes());
+            assert_eq!(answer[12..], rdata[..]);
+        }
+
+        let packet = query("ads.example.com", 15);
+        let question = parse_question(&packet).unwrap();
+        let response = sinkhole_response(&packet, &question, ipv4, ipv6);
+        assert_eq!(response.len(), question.end);
+        assert_eq!(response[6..8], [0, 0]);
+    }
+
+    #[test]
+    fn malformed_queries_do_not_panic() {
+        // 固定种子的伪随机数据，覆盖各种长度字节与截断位置的组合
+        let mut seed: u32 = 0x2545_F491;
+        let mut next = move || {
+            seed ^= seed << 13;
+            seed ^= seed >> 17;
+            seed ^= seed << 5;
+            seed
+        };
+        for _ in 0..20_000 {
+            let len = 12 + (next() % 64) as usize;
+            let mut packet: Vec<u8> = (0..len).map(|_| (next() % 70) as u8).collect();
+            packet[..12].copy_from_slice(&HEADER);
+            if let Some(question) = parse_question(&packet) {
+                assert!(question.end <= packet.len());
+                empty_response(&packet, question.end, RCODE_SERVFAIL);
+                sinkhole_response(&packet, &question, Ipv4Addr::UNSPECIFIED, Ipv6Addr::UNSPECIFIED);
+            }
+        }
+    }
+}
@@ -0,0 +1,144 @@
+//! In-memory blockers for exercising commands without touching the system.
+
+use super::apps::AppBlocker;
+use super::process::{
+    KillReport, ProcessError, ProcessInfo, ProcessMatcher, TerminateOptions, TerminationOutcome,
+    TerminationResult,
+};
+use super::rules::DomainRule;
+use super::website::{BlockerBackend, BlockerError, WebsiteBlocker};
+use std::sync::{Mutex, MutexGuard};
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+#[derive(Default)]
+pub struct MemoryWebsiteBlocker {
+    domains: Mutex<Vec<String>>,
+}
+
+impl MemoryWebsiteBlocker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl WebsiteBlocker for MemoryWebsiteBlocker {
+    fn backend(&self) -> BlockerBackend {
+        BlockerBackend::Memory
+    }
+
+    fn list(&self) -> Result<Vec<String>, BlockerError> {
+        Ok(lock(&self.domains).clone())
+    }
+
+    fn block(&self, domains: &[String]) -> Result<(), BlockerError> {
+        let mut blocked = lock(&self.domains);
+        for domain in domains {
+            let rule = DomainRule::parse(domain).ok_or_else(|| BlockerError::InvalidRule(domain.clone()))?;
+            let rule = rule.to_string();
+            if !blocked.contains(&rule) {
+                blocked.push(rule);
+            }
+        }
+        Ok(())
+    }
+
+    fn unblock(&self, domain: &str) -> Result<(), BlockerError> {
+        // 与真实后端一致，按规范化后的规则匹配
+        let rule = DomainRule::parse(domain)
+            .map(|rule| rule.to_string())
+            .unwrap_or_else(|| domain.trim().to_lowercase());
+        lock(&self.domains).retain(|d| *d != rule);
+        Ok(())
+    }
+
+    fn clear(&self) -> Result<(), BlockerError> {
+        lock(&self.domains).clear();
+        Ok(())
+    }
+}
+
+/// A fixed process table; killed processes are removed from it and recorded.
+#[derive(Default)]
+pub struct MemoryAppBlocker {
+    processes: Mutex<Vec<ProcessInfo>>,
+    killed: Mutex<Vec<u32>>,
+}
+
+impl MemoryAppBlocker {
+    pub fn new(processes: Vec<ProcessInfo>) -> Self {
+        MemoryAppBlocker {
+            processes: Mutex::new(processes),
+            killed: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn killed(&self) -> Vec<u32> {
+        lock(&self.killed).clone()
+    }
+
+    fn terminate(&self, targets: Vec<ProcessInfo>) -> KillReport {
+        let mut processes = lock(&self.processes);
+        let mut killed = lock(&self.killed);
+        let mut report = KillReport::default();
+
+        for target in targets {
+            processes.retain(|p| p.pid != target.pid);
+            killed.push(target.pid);
+            report.killed += 1;
+            report.results.push(TerminationResult {
+                pid: target.pid,
+                name: target.name,
+                outcome: TerminationOutcome::Terminated,
+            });
+        }
+        report
+    }
+}
+
+impl AppBlocker for MemoryAppBlocker {
+    fn refresh(&self) -> Result<(), ProcessError> {
+        Ok(())
+    }
+
+    fn find(&self, matcher: &ProcessMatcher) -> Result<Vec<ProcessInfo>, ProcessError> {
+        self.running(std::slice::from_ref(matcher))
+    }
+
+    fn running(&self, matchers: &[ProcessMatcher]) -> Result<Vec<ProcessInfo>, ProcessError> {
+        let compiled = matchers
+            .iter()
+            .map(|m| m.compile())
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(lock(&self.processes)
+            .iter()
+            .filter(|p| compiled.iter().any(|m| m.matches_info(p)))
+            .cloned()
+            .collect())
+    }
+
+    fn kill_pid(&self, pid: u32, options: &TerminateOptions) -> Result<KillReport, ProcessError> {
+        let processes = lock(&self.processes).clone();
+        let target = processes
+            .iter()
+            .find(|p| p.pid == pid)
+            .cloned()
+            .ok_or(ProcessError::NotFound(pid))?;
+        let mut targets = vec![target];
+        // 与真实实现一样按 parent_pid 逐层收集子孙进程
+        let mut index = 0;
+        while options.kill_tree && index < targets.len() {
+            let parent = targets[index].pid;
+            targets.extend(processes.iter().filter(|p| p.parent_pid == Some(parent)).cloned());
+            index += 1;
+        }
+        Ok(self.terminate(targets))
+    }
+
+    fn kill_matching(&self, matchers: &[ProcessMatcher], _options: &TerminateOptions) -> Result<KillReport, ProcessError> {
+        let targets = self.running(matchers)?;
+        Ok(self.terminate(targets))
+    }
+}
@@ -0,0 +1,179 @@
+use super::process::{get_process_info, ProcessError, ProcessInfo};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ForegroundError {
+    #[error("Active window detection is not supported here: {0}")]
+    Unsupported(String),
+    #[error("Failed to query the active window: {0}")]
+    Query(String),
+    #[error("Process error: {0}")]
+    Process(#[from] ProcessError),
+}
+
+/// The focused window and the process that owns it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActiveWindow {
+    pub title: String,
+    pub process: ProcessInfo,
+}
+
+/// Samples the focused window. Returns `None` when nothing has focus, e.g. on the
+/// lock screen or when the owning process has already exited.
+pub fn active_window() -> Result<Option<ActiveWindow>, ForegroundError> {
+    let Some((pid, title)) = platform::focused_window()? else {
+        return Ok(None);
+    };
+    Ok(get_process_info(pid)?.map(|process| ActiveWindow { title, process }))
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use super::ForegroundError;
+
+    #[link(name = "user32")]
+    extern "system" {
+        fn GetForegroundWindow() -> isize;
+        fn GetWindowThreadProcessId(hwnd: isize, pid: *mut u32) -> u32;
+        fn GetWindowTextW(hwnd: isize, text: *mut u16, max_count: i32) -> i32;
+    }
+
+    pub fn focused_window() -> Result<Option<(u32, String)>, ForegroundError> {
+        unsafe {
+            let hwnd = GetForegroundWindow();
+            if hwnd == 0 {
+                return Ok(None);
+            }
+
+            let mut pid = 0u32;
+            GetWindowThreadProcessId(hwnd, &mut pid);
+            if pid == 0 {
+                return Ok(None);
+            }
+
+            let mut buf = [0u16; 512];
+            let len = GetWindowTextW(hwnd, buf.as_mut_ptr(), buf.len() as i32);
+            let title = String::from_utf16_lossy(&buf[..len.max(0) as usize]);
+            Ok(Some((pid, title)))
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::ForegroundError;
+    use std::process::Command;
+
+    // 读取窗口标题需要辅助功能权限，没有权限时只返回空标题
+    const SCRIPT: &str = r#"tell application "System Events"
+    set frontApp to first application process whose frontmost is true
+    set windowTitle to ""
+    try
+        set windowTitle to name of front window of frontApp
+    end try
+    return (unix id of frontApp as text) & linefeed & windowTitle
+end tell"#;
+
+    pub fn focused_window() -> Result<Option<(u32, String)>, ForegroundError> {
+        let output = Command::new("osascript")
+            .args(["-e", SCRIPT])
+            .output()
+            .map_err(|e| ForegroundError::Query(e.to_string()))?;
+        if !output.status.success() {
+            return Err(ForegroundError::Query(
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            ));
+        }
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let mut lines = stdout.lines();
+        let pid = match lines.next().and_then(|line| line.trim().parse::<u32>().ok()) {
+            Some(pid) => pid,
+            None => return Ok(None),
+        };
+        Ok(Some((pid, lines.next().unwrap_or_default().to_string())))
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+mod platform {
+    use super::ForegroundError;
+    use std::process::Command;
+
+    fn run(program: &str, args: &[&str]) -> Option<String> {
+        let output = Command::new(program).args(args).output().ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        Some(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+
+    // Wayland 没有通用的前台窗口接口，只能依赖各合成器自己的 IPC；
+    // X11 会话（以及 XWayland）用 EWMH 属性
+    pub fn focused_window() -> Result<Option<(u32, String)>, ForegroundError> {
+        if std::env::var_os("HYPRLAND_INSTANCE_SIGNATURE").is_some() {
+            return Ok(run("hyprctl", &["activewindow", "-j"]).and_then(|out| parse_hyprland(&out)));
+        }
+        if std::env::var_os("SWAYSOCK").is_some() {
+            return Ok(run("swaymsg", &["-t", "get_tree"]).and_then(|out| parse_sway(&out)));
+        }
+        if std::env::var_os("DISPLAY").is_some() {
+            return x11_focused_window();
+        }
+        Err(ForegroundError::Unsupported(
+            "this Wayland compositor does not expose the focused window".to_string(),
+        ))
+    }
+
+    fn parse_hyprland(output: &str) -> Option<(u32, String)> {
+        let window: serde_json::Value = serde_json::from_str(output).ok()?;
+        let pid = window.get("pid")?.as_u64()?;
+        let title = window.get("title").and_then(|t| t.as_str()).unwrap_or_default();
+        Some((pid as u32, title.to_string()))
+    }
+
+    fn parse_sway(output: &str) -> Option<(u32, String)> {
+        let tree: serde_json::Value = serde_json::from_str(output).ok()?;
+        find_focused_node(&tree)
+    }
+
+    fn find_focused_node(node: &serde_json::Value) -> Option<(u32, String)> {
+        if node.get("focused").and_then(|f| f.as_bool()).unwrap_or(false) {
+            if let Some(pid) = node.get("pid").and_then(|p| p.as_u64()) {
+                let title = node.get("name").and_then(|n| n.as_str()).unwrap_or_default();
+                return Some((pid as u32, title.to_string()));
+            }
+        }
+        ["nodes", "floating_nodes"]
+            .iter()
+            .filter_map(|key| node.get(*key).and_then(|n| n.as_array()))
+            .flatten()
+            .find_map(find_focused_node)
+    }
+
+    fn x11_focused_window() -> Result<Option<(u32, String)>, ForegroundError> {
+        let root = run("xprop", &["-root", "_NET_ACTIVE_WINDOW"])
+            .ok_or_else(|| ForegroundError::Query("xprop is not available".to_string()))?;
+        // _NET_ACTIVE_WINDOW(WINDOW): window id # 0x3a00007
+        let window = match root.split_whitespace().last() {
+            Some(id) if id.starts_with("0x") && id != "0x0" => id.to_string(),
+            _ => return Ok(None),
+        };
+
+        let props = run("xprop", &["-id", &window, "_NET_WM_PID", "_NET_WM_NAME"])
+            .ok_or_else(|| ForegroundError::Query(format!("failed to read window {}", window)))?;
+        let mut pid = None;
+        let mut title = String::new();
+        for line in props.lines() {
+            if let Some(value) = line.strip_prefix("_NET_WM_PID(CARDINAL) = ") {
+                pid = value.trim().parse::<u32>().ok();
+            } else if let Some((_, value)) = line.split_once(") = ") {
+                if line.starts_with("_NET_WM_NAME") {
+                    title = value.trim().trim_matches('"').replace("\\\"", "\"");
+                }
+            }
+        }
+        Ok(pid.map(|pid| (pid, title)))
+    }
+}
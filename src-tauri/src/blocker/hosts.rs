@@ -1,7 +1,9 @@
-use std::fs::{self, OpenOptions};
-use std::io::{Read, Write, BufRead, BufReader};
-use std::path::Path;
+use std::fs;
 use thiserror::Error;
+use super::dns::{self, DnsError, ResolverStatus};
+use super::rules::{is_hostname, DomainRule, RuleKind};
+use std::path::PathBuf;
+use super::website::{BlockerBackend, BlockerError, WebsiteBlocker};
 
 #[cfg(target_os = "windows")]
 const HOSTS_PATH: &str = r"C:\Windows\System32\drivers\etc\hosts";
@@ -20,9 +22,34 @@ pub enum HostsError {
     WriteError(std::io::Error),
     #[error("Permission denied: {0}")]
     PermissionError(String),
+    #[error(transparent)]
+    ResolverError(#[from] DnsError),
 }
 
 const BLOCK_COMMENT: &str = "# Focus Keeper Block";
+// 通配规则展开出的条目以 rule=<规则> 标记，便于整体刷新和删除
+const RULE_TAG: &str = "rule=";
+const MAX_HISTORY: usize = 5000;
+
+/// Prefixes tried for every wildcard rule, whether or not they were ever seen.
+const COMMON_SUBDOMAINS: &[&str] = &["www", "m", "mobile", "app", "web", "api", "login", "accounts"];
+
+/// Well-known subdomains of frequently blocked sites.
+const KNOWN_SUBDOMAINS: &[(&str, &[&str])] = &[
+    ("youtube.com", &["music", "studio", "gaming", "tv", "kids"]),
+    ("reddit.com", &["old", "new", "i", "np", "out", "gateway", "oauth", "sh"]),
+    ("twitter.com", &["mobile", "tweetdeck", "pro", "abs", "pbs"]),
+    ("x.com", &["pro", "abs", "pbs"]),
+    ("facebook.com", &["business", "touch", "mbasic", "l", "lm", "static"]),
+    ("instagram.com", &["i", "graph", "about", "help"]),
+    ("tiktok.com", &["vm", "vt", "us", "ads", "lf16-tiktok-web"]),
+    ("twitch.tv", &["clips", "dashboard", "player", "gql", "static"]),
+    ("netflix.com", &["help", "assets", "api-global", "ichnaea"]),
+    ("bilibili.com", &["live", "space", "search", "t", "manga", "api"]),
+    ("weibo.com", &["s", "passport", "d", "card"]),
+    ("zhihu.com", &["zhuanlan", "api", "static", "pic1"]),
+    ("douyin.com", &["live", "creator", "v"]),
+];
 
 pub fn get_hosts_path() -> String {
     HOSTS_PATH.to_string()
@@ -56,14 +83,40 @@ pub fn read_blocked_websites() -> Result<Vec<String>, HostsError> {
         }
     }
 
+    let mut seen = std::collections::HashSet::new();
+    blocked.retain(|domain| seen.insert(domain.clone()));
     Ok(blocked)
 }
 
+// 条目行的主机名字段；注释行（包括通配规则的段落标题）没有
+fn entry_host(line: &str) -> Option<&str> {
+    if line.trim_start().starts_with('#') {
+        return None;
+    }
+    line.split_whitespace().nth(1)
+}
+
+// 由精确规则直接写入的条目：带屏蔽标记但没有 rule= 标签，通配规则展开的条目不算
+fn is_exact_entry(line: &str, domain: &str) -> bool {
+    line.contains(BLOCK_COMMENT)
+        && rule_tag(line).is_none()
+        && entry_host(line).is_some_and(|host| host.eq_ignore_ascii_case(domain))
+}
+
+fn exact_entries(content: &str) -> std::collections::HashSet<String> {
+    content
+        .lines()
+        .filter(|line| line.contains(BLOCK_COMMENT) && rule_tag(line).is_none())
+        .filter_map(entry_host)
+        .map(|host| host.to_lowercase())
+        .collect()
+}
+
 pub fn add_blocked_website(domain: &str) -> Result<(), HostsError> {
     let mut content = fs::read_to_string(HOSTS_PATH)
         .map_err(HostsError::ReadError)?;
 
-    if !content.contains(&format!("127.0.0.1 {}", domain)) {
+    if !content.lines().any(|line| is_exact_entry(line, domain)) {
         if !content.ends_with('\n') {
             content.push('\n');
         }
@@ -82,7 +135,7 @@ pub fn remove_blocked_website(domain: &str) -> Result<(), HostsError> {
 
     let new_content: String = content
         .lines()
-        .filter(|line| !line.contains(&format!("127.0.0.1 {}", domain)))
+        .filter(|line| !is_exact_entry(line, domain))
         .collect::<Vec<_>>()
         .join("\n");
 
@@ -96,11 +149,12 @@ pub fn add_blocked_websites(domains: &[String]) -> Result<(), HostsError> {
     let mut content = fs::read_to_string(HOSTS_PATH)
         .map_err(HostsError::ReadError)?;
 
-    let existing_blocked = read_blocked_websites().unwrap_or_default();
+    // 只和精确条目比较：已被通配规则展开覆盖的域名仍要单独记录，否则不会出现在规则列表里
+    let mut existing_blocked = exact_entries(&content);
 
     let mut new_entries = Vec::new();
     for domain in domains {
-        if !existing_blocked.contains(domain) {
+        if existing_blocked.insert(domain.to_lowercase()) {
             new_entries.push(format!("127.0.0.1 {} # {}", domain, BLOCK_COMMENT));
         }
     }
@@ -135,6 +189,275 @@ pub fn clear_all_blocks() -> Result<(), HostsError> {
     Ok(())
 }
 
+fn rule_tag(line: &str) -> Option<&str> {
+    line.split_whitespace().find_map(|token| token.strip_prefix(RULE_TAG))
+}
+
+/// The rules behind the hosts entries: plain domains plus each wildcard rule once.
+pub fn read_block_rules() -> Result<Vec<String>, HostsError> {
+    let content = fs::read_to_string(HOSTS_PATH)
+        .map_err(HostsError::ReadError)?;
+
+    let mut rules: Vec<String> = Vec::new();
+    for line in content.lines().filter(|line| line.contains(BLOCK_COMMENT)) {
+        let rule = match rule_tag(line) {
+            Some(rule) => rule.to_string(),
+            None => match line.split_whitespace().nth(1) {
+                Some(domain) if !line.trim_start().starts_with('#') => domain.to_string(),
+                _ => continue,
+            },
+        };
+        if !rules.contains(&rule) {
+            rules.push(rule);
+        }
+    }
+    Ok(rules)
+}
+
+fn get_history_path() -> PathBuf {
+    let path = dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("FocusKeeper")
+        .join("hosts-history.json");
+
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    path
+}
+
+fn load_history() -> Vec<String> {
+    fs::read_to_string(get_history_path())
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+// 记住见过的域名，DNS 缓存被清空后下次展开仍然能覆盖到
+fn record_history(domains: &[String]) {
+    let mut history = load_history();
+    for domain in domains {
+        if !history.contains(domain) {
+            history.push(domain.clone());
+        }
+    }
+    if history.len() > MAX_HISTORY {
+        history.drain(..history.len() - MAX_HISTORY);
+    }
+    if let Ok(content) = serde_json::to_string(&history) {
+        let _ = fs::write(get_history_path(), content);
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn dns_cache_domains() -> Vec<String> {
+    use std::process::Command;
+    let output = match Command::new("ipconfig").arg("/displaydns").output() {
+        Ok(output) => output,
+        Err(_) => return Vec::new(),
+    };
+    // 字段名会被本地化，只取冒号后面像域名的值
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.split_once(" : ").map(|(_, value)| value.trim().to_lowercase()))
+        .collect()
+}
+
+#[cfg(target_os = "linux")]
+fn dns_cache_domains() -> Vec<String> {
+    use std::process::Command;
+    // systemd-resolved 250 起支持导出缓存
+    let output = match Command::new("resolvectl").arg("show-cache").output() {
+        Ok(output) => output,
+        Err(_) => return Vec::new(),
+    };
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.split_whitespace().next())
+        .map(|name| name.trim_end_matches('.').to_lowercase())
+        .collect()
+}
+
+#[cfg(target_os = "macos")]
+fn dns_cache_domains() -> Vec<String> {
+    // mDNSResponder 没有可直接读取缓存的接口
+    Vec::new()
+}
+
+fn observed_domains() -> Vec<String> {
+    let mut domains = dns_cache_domains();
+    domains.retain(|d| is_hostname(d));
+    record_history(&domains);
+
+    domains.extend(load_history());
+    if let Ok(content) = fs::read_to_string(HOSTS_PATH) {
+        domains.extend(
+            content
+                .lines()
+                .filter(|line| !line.trim_start().starts_with('#'))
+                .flat_map(|line| line.split_whitespace().skip(1).take_while(|t| !t.starts_with('#')))
+                .map(|name| name.to_lowercase()),
+        );
+    }
+    domains
+}
+
+/// Concrete hostnames a wildcard rule currently covers.
+pub fn expand_rule(rule: &DomainRule) -> Vec<String> {
+    expand_rule_with(rule, &observed_domains())
+}
+
+fn expand_rule_with(rule: &DomainRule, observed: &[String]) -> Vec<String> {
+    let mut candidates: Vec<String> = Vec::new();
+    if rule.kind == RuleKind::Suffix {
+        candidates.push(rule.domain.clone());
+    }
+    candidates.extend(COMMON_SUBDOMAINS.iter().map(|sub| format!("{}.{}", sub, rule.domain)));
+    for (domain, subdomains) in KNOWN_SUBDOMAINS {
+        if rule.matches(&format!("x.{}", domain)) || *domain == rule.domain {
+            candidates.extend(subdomains.iter().map(|sub| format!("{}.{}", sub, domain)));
+        }
+    }
+    candidates.extend(observed.iter().cloned());
+
+    let mut expanded: Vec<String> = candidates
+        .into_iter()
+        .filter(|name| rule.matches(name))
+        .collect();
+    expanded.sort();
+    expanded.dedup();
+    expanded
+}
+
+fn remove_rule_lines(content: &str, rule: &str) -> String {
+    content
+        .lines()
+        .filter(|line| !(line.contains(BLOCK_COMMENT) && rule_tag(line) == Some(rule)))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Blocks wildcard rules, writing each as its current expansion. Returns the
+/// number of concrete entries written.
+pub fn add_block_rules(rules: &[DomainRule]) -> Result<usize, HostsError> {
+    let rules: Vec<&DomainRule> = rules.iter().filter(|r| r.is_wildcard()).collect();
+    if rules.is_empty() {
+        return Ok(0);
+    }
+
+    let observed = observed_domains();
+    let mut new_content = fs::read_to_string(HOSTS_PATH)
+        .map_err(HostsError::ReadError)?;
+    let mut total = 0;
+
+    for rule in rules {
+        let expanded = expand_rule_with(rule, &observed);
+        let tag = rule.to_string();
+        new_content = remove_rule_lines(&new_content, &tag);
+        new_content.push_str(&format!("\n{} {}{}\n", BLOCK_COMMENT, RULE_TAG, tag));
+        for domain in &expanded {
+            new_content.push_str(&format!("127.0.0.1 {} # {} {}{}\n", domain, BLOCK_COMMENT, RULE_TAG, tag));
+        }
+        total += expanded.len();
+    }
+
+    fs::write(HOSTS_PATH, new_content)
+        .map_err(HostsError::WriteError)?;
+    Ok(total)
+}
+
+pub fn remove_block_rule(rule: &DomainRule) -> Result<(), HostsError> {
+    if !rule.is_wildcard() {
+        return remove_blocked_website(&rule.domain);
+    }
+
+    let content = fs::read_to_string(HOSTS_PATH)
+        .map_err(HostsError::ReadError)?;
+    let new_content = remove_rule_lines(&content, &rule.to_string());
+
+    fs::write(HOSTS_PATH, format!("{}\n", new_content))
+        .map_err(HostsError::WriteError)?;
+    Ok(())
+}
+
+/// Re-expands every wildcard rule, picking up subdomains seen since it was added.
+pub fn refresh_block_rules() -> Result<usize, HostsError> {
+    let rules: Vec<DomainRule> = read_block_rules()?
+        .iter()
+        .filter_map(|r| DomainRule::parse(r))
+        .collect();
+    add_block_rules(&rules)
+}
+
+/// Switches to "allow only these domains": every other lookup fails at the local resolver.
+/// The hosts-file blocks above still apply on top of it.
+pub fn enable_allow_only(domains: &[String]) -> Result<(), HostsError> {
+    if !is_admin() {
+        return Err(HostsError::PermissionError(
+            "changing system DNS requires administrator privileges".to_string(),
+        ));
+    }
+    dns::set_allow_only(Some(domains))?;
+    Ok(())
+}
+
+pub fn disable_allow_only() -> Result<(), HostsError> {
+    dns::set_allow_only(None)?;
+    Ok(())
+}
+
+pub fn allow_only_status() -> ResolverStatus {
+    dns::resolver_status()
+}
+
+/// The original hosts-file backend. Wildcard rules are expanded to concrete entries.
+pub struct HostsBlocker;
+
+impl WebsiteBlocker for HostsBlocker {
+    fn backend(&self) -> BlockerBackend {
+        BlockerBackend::Hosts
+    }
+
+    fn list(&self) -> Result<Vec<String>, BlockerError> {
+        Ok(read_block_rules()?)
+    }
+
+    fn entries(&self) -> Result<Vec<String>, BlockerError> {
+        Ok(read_blocked_websites()?)
+    }
+
+    fn block(&self, domains: &[String]) -> Result<(), BlockerError> {
+        let rules = domains
+            .iter()
+            .map(|d| DomainRule::parse(d).ok_or_else(|| BlockerError::InvalidRule(d.clone())))
+            .collect::<Result<Vec<_>, _>>()?;
+        let exact: Vec<String> = rules
+            .iter()
+            .filter(|r| !r.is_wildcard())
+            .map(|r| r.domain.clone())
+            .collect();
+        add_blocked_websites(&exact)?;
+        add_block_rules(&rules)?;
+        Ok(())
+    }
+
+    fn unblock(&self, domain: &str) -> Result<(), BlockerError> {
+        match DomainRule::parse(domain) {
+            Some(rule) => Ok(remove_block_rule(&rule)?),
+            None => Ok(remove_blocked_website(domain)?),
+        }
+    }
+
+    fn clear(&self) -> Result<(), BlockerError> {
+        Ok(clear_all_blocks()?)
+    }
+
+    fn refresh(&self) -> Result<(), BlockerError> {
+        refresh_block_rules()?;
+        Ok(())
+    }
+}
+
 pub fn is_admin() -> bool {
     #[cfg(target_os = "windows")]
     {
@@ -0,0 +1,132 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum IdleError {
+    #[error("Idle detection is not supported here: {0}")]
+    Unsupported(String),
+    #[error("Failed to query idle time: {0}")]
+    Query(String),
+}
+
+/// Seconds since the last keyboard or mouse input in the user's session.
+pub fn idle_seconds() -> Result<u64, IdleError> {
+    platform::idle_millis().map(|ms| ms / 1000)
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use super::IdleError;
+
+    #[repr(C)]
+    struct LastInputInfo {
+        cb_size: u32,
+        dw_time: u32,
+    }
+
+    #[link(name = "user32")]
+    extern "system" {
+        fn GetLastInputInfo(plii: *mut LastInputInfo) -> i32;
+    }
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetTickCount() -> u32;
+    }
+
+    pub fn idle_millis() -> Result<u64, IdleError> {
+        let mut info = LastInputInfo {
+            cb_size: std::mem::size_of::<LastInputInfo>() as u32,
+            dw_time: 0,
+        };
+        unsafe {
+            if GetLastInputInfo(&mut info) == 0 {
+                return Err(IdleError::Query(std::io::Error::last_os_error().to_string()));
+            }
+            // 两者都是 32 位毫秒计数，约 49.7 天回绕一次
+            Ok(u64::from(GetTickCount().wrapping_sub(info.dw_time)))
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::IdleError;
+    use std::process::Command;
+
+    pub fn idle_millis() -> Result<u64, IdleError> {
+        let output = Command::new("ioreg")
+            .args(["-c", "IOHIDSystem", "-d", "4"])
+            .output()
+            .map_err(|e| IdleError::Query(e.to_string()))?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+
+        // "HIDIdleTime" = 1234567890（纳秒）
+        stdout
+            .lines()
+            .find_map(|line| {
+                let (_, value) = line.split_once("\"HIDIdleTime\" = ")?;
+                value.trim().parse::<u64>().ok()
+            })
+            .map(|ns| ns / 1_000_000)
+            .ok_or_else(|| IdleError::Query("HIDIdleTime not found".to_string()))
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+mod platform {
+    use super::IdleError;
+    use std::process::Command;
+
+    fn run(program: &str, args: &[&str]) -> Option<String> {
+        let output = Command::new(program).args(args).output().ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        Some(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+
+    // dbus-send --print-reply 的输出形如 "method return ...\n   uint64 12345"
+    fn dbus_millis(dest: &str, path: &str, method: &str) -> Option<u64> {
+        let output = run(
+            "dbus-send",
+            &["--session", "--print-reply", &format!("--dest={}", dest), path, method],
+        )?;
+        output.split_whitespace().last()?.parse().ok()
+    }
+
+    fn mutter_idle() -> Option<u64> {
+        dbus_millis(
+            "org.gnome.Mutter.IdleMonitor",
+            "/org/gnome/Mutter/IdleMonitor/Core",
+            "org.gnome.Mutter.IdleMonitor.GetIdletime",
+        )
+    }
+
+    fn screensaver_idle() -> Option<u64> {
+        dbus_millis(
+            "org.freedesktop.ScreenSaver",
+            "/org/freedesktop/ScreenSaver",
+            "org.freedesktop.ScreenSaver.GetSessionIdleTime",
+        )
+    }
+
+    fn xprintidle() -> Option<u64> {
+        run("xprintidle", &[])?.trim().parse().ok()
+    }
+
+    // Wayland 下只有桌面环境自己知道输入状态：GNOME 用 Mutter 的 IdleMonitor，
+    // KDE 用 freedesktop ScreenSaver；X11 会话用 xprintidle
+    pub fn idle_millis() -> Result<u64, IdleError> {
+        let wayland = std::env::var_os("WAYLAND_DISPLAY").is_some();
+        let found = if wayland {
+            mutter_idle().or_else(screensaver_idle)
+        } else {
+            xprintidle().or_else(mutter_idle).or_else(screensaver_idle)
+        };
+        found.ok_or_else(|| {
+            IdleError::Unsupported(
+                "no idle time source found (install xprintidle or use GNOME/KDE)".to_string(),
+            )
+        })
+    }
+}
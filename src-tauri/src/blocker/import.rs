@@ -0,0 +1,499 @@
+use super::rules::{DomainRule, RuleKind};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportFormat {
+    #[default]
+    Auto,
+    /// One domain or rule per line.
+    Plain,
+    /// `0.0.0.0 domain` / `127.0.0.1 domain`.
+    Hosts,
+    /// `||domain^` network rules.
+    Adblock,
+    /// uBlock Origin backup JSON, or its "My filters" text.
+    Ublock,
+    /// LeechBlock NG options export (`sites1=...`).
+    Leechblock,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportEntry {
+    /// 1-based line number in the imported content.
+    pub line: usize,
+    pub text: String,
+    pub rule: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RejectedLine {
+    pub line: usize,
+    pub text: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ImportPreview {
+    pub format: ImportFormat,
+    /// New rules that will be added, one entry per rule.
+    pub accepted: Vec<ImportEntry>,
+    /// Accepted entries whose rule differs from the text as written.
+    pub normalized: Vec<ImportEntry>,
+    /// Rules repeated in the input or already on the block list.
+    pub duplicates: Vec<ImportEntry>,
+    pub rejected: Vec<RejectedLine>,
+}
+
+impl ImportPreview {
+    pub fn rules(&self) -> Vec<String> {
+        self.accepted.iter().map(|e| e.rule.clone()).collect()
+    }
+}
+
+const LOCAL_HOSTNAMES: &[&str] = &[
+    "localhost",
+    "localhost.localdomain",
+    "local",
+    "broadcasthost",
+    "ip6-localhost",
+    "ip6-loopback",
+    "ip6-localnet",
+    "ip6-mcastprefix",
+    "ip6-allnodes",
+    "ip6-allrouters",
+    "ip6-allhosts",
+    "0.0.0.0",
+];
+
+/// Adblock options that don't change what a domain-level block means.
+const HARMLESS_OPTIONS: &[&str] = &["important", "all", "document", "doc", "popup", "third-party", "3p", "first-party", "1p"];
+
+type LineResult = Result<Vec<String>, String>;
+
+fn detect_format(content: &str) -> ImportFormat {
+    if content.trim_start().starts_with('{') {
+        return ImportFormat::Ublock;
+    }
+
+    let lines: Vec<&str> = content
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
+        .collect();
+
+    if lines.iter().any(|line| is_leechblock_sites_key(line)) {
+        ImportFormat::Leechblock
+    } else if lines
+        .iter()
+        .any(|line| line.starts_with("||") || line.starts_with("[Adblock") || line.starts_with("@@"))
+    {
+        ImportFormat::Adblock
+    } else if lines
+        .iter()
+        .filter(|line| !line.starts_with('#'))
+        .any(|line| line.split_whitespace().next().map(|t| t.parse::<std::net::IpAddr>().is_ok()).unwrap_or(false))
+    {
+        ImportFormat::Hosts
+    } else {
+        ImportFormat::Plain
+    }
+}
+
+fn is_leechblock_sites_key(line: &str) -> bool {
+    line.split_once('=')
+        .and_then(|(key, _)| key.strip_prefix("sites"))
+        .map(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+        .unwrap_or(false)
+}
+
+fn is_comment(line: &str) -> bool {
+    line.is_empty() || line.starts_with('#') || line.starts_with('!') || line.starts_with("//") || line.starts_with('[')
+}
+
+fn parse_rule(text: &str) -> Result<String, String> {
+    DomainRule::parse(text)
+        .map(|rule| rule.to_string())
+        .ok_or_else(|| "not a valid domain".to_string())
+}
+
+// 去掉协议和端口；带具体路径的规则无法在域名层面表达
+fn strip_url(text: &str) -> Result<&str, String> {
+    let text = text
+        .strip_prefix("https://")
+        .or_else(|| text.strip_prefix("http://"))
+        .unwrap_or(text);
+    let (host, path) = match text.find('/') {
+        Some(index) => (&text[..index], &text[index..]),
+        None => (text, ""),
+    };
+    if !(path.is_empty() || path == "/" || path == "/**") {
+        return Err("path rules are not supported".to_string());
+    }
+    let host = host.split(':').next().unwrap_or(host);
+    Ok(if path == "/**" { text.trim_end_matches('/') } else { host })
+}
+
+fn parse_plain(line: &str) -> LineResult {
+    let text = line.split(" #").next().unwrap_or(line).trim();
+    Ok(vec![parse_rule(strip_url(text)?)?])
+}
+
+fn parse_hosts(line: &str) -> LineResult {
+    let line = line.split('#').next().unwrap_or(line);
+    let mut tokens = line.split_whitespace();
+    let address = tokens.next().ok_or("empty line")?;
+    if address.parse::<std::net::IpAddr>().is_err() {
+        return Err("expected an IP address".to_string());
+    }
+
+    let rules: Vec<String> = tokens
+        .filter(|name| !LOCAL_HOSTNAMES.contains(&name.to_lowercase().as_str()))
+        .map(parse_rule)
+        .collect::<Result<_, _>>()?;
+    if rules.is_empty() {
+        return Err("no blockable hostnames".to_string());
+    }
+    Ok(rules)
+}
+
+fn parse_adblock(line: &str) -> LineResult {
+    if line.starts_with("@@") {
+        return Err("exception rules are not supported".to_string());
+    }
+    if line.contains("##") || line.contains("#@#") || line.contains("#?#") {
+        return Err("cosmetic filters are not supported".to_string());
+    }
+
+    let (pattern, options) = match line.split_once('$') {
+        Some((pattern, options)) => (pattern, Some(options)),
+        None => (line, None),
+    };
+    if let Some(options) = options {
+        if let Some(option) = options
+            .split(',')
+            .map(|o| o.trim().trim_start_matches('~'))
+            .find(|o| !HARMLESS_OPTIONS.contains(o))
+        {
+            return Err(format!("unsupported filter option: {}", option));
+        }
+    }
+
+    if let Some(domain) = pattern.strip_prefix("||") {
+        let domain = domain.strip_suffix('^').unwrap_or(domain);
+        if domain.contains(['/', '*', '^']) {
+            return Err("path rules are not supported".to_string());
+        }
+        // ||domain^ 同时匹配域名本身和所有子域名
+        let rule = DomainRule::parse(domain).ok_or("not a valid domain")?;
+        return Ok(vec![DomainRule {
+            kind: RuleKind::Suffix,
+            domain: rule.domain,
+        }
+        .to_string()]);
+    }
+    if let Some(url) = pattern.strip_prefix('|') {
+        let url = url.trim_end_matches('|');
+        return Ok(vec![parse_rule(strip_url(url)?)?]);
+    }
+    Err("only domain rules (||domain^) are supported".to_string())
+}
+
+fn parse_ublock_dynamic(line: &str) -> LineResult {
+    // 动态过滤规则：<来源> <目标> <类型> <动作>
+    let tokens: Vec<&str> = line.split_whitespace().collect();
+    match tokens.as_slice() {
+        ["*", host, "*", "block"] => Ok(vec![DomainRule {
+            kind: RuleKind::Suffix,
+            domain: DomainRule::parse(host).ok_or("not a valid domain")?.domain,
+        }
+        .to_string()]),
+        _ => Err("only global block rules (* host * block) are supported".to_string()),
+    }
+}
+
+fn parse_leechblock_site(site: &str) -> Result<String, String> {
+    if site.starts_with('+') {
+        return Err("allow exceptions are not supported".to_string());
+    }
+    let site = strip_url(site)?;
+    // strip_url 保留的 /** 后缀不算通配符
+    if site.trim_end_matches("/**").contains('*') && !site.starts_with("*.") {
+        return Err("only leading *. wildcards are supported".to_string());
+    }
+    let rule = DomainRule::parse(site).ok_or("not a valid domain")?;
+    // LeechBlock 中的 example.com 也会匹配其子域名
+    Ok(match rule.kind {
+        RuleKind::Exact => DomainRule {
+            kind: RuleKind::Suffix,
+            domain: rule.domain,
+        }
+        .to_string(),
+        _ => rule.to_string(),
+    })
+}
+
+struct Collector {
+    preview: ImportPreview,
+    existing: HashSet<String>,
+    seen: HashSet<String>,
+}
+
+impl Collector {
+    fn push(&mut self, line: usize, text: &str, result: LineResult) {
+        let rules = match result {
+            Ok(rules) => rules,
+            Err(reason) => {
+                self.preview.rejected.push(RejectedLine {
+                    line,
+                    text: text.to_string(),
+                    reason,
+                });
+                return;
+            }
+        };
+
+        for rule in rules {
+            let entry = ImportEntry {
+                line,
+                text: text.to_string(),
+                rule: rule.clone(),
+            };
+            if self.existing.contains(&rule) || !self.seen.insert(rule.clone()) {
+                self.preview.duplicates.push(entry);
+                continue;
+            }
+            // hosts 等格式一行含多个字段，只要某个字段与规则一致就不算改写
+            if !text.split_whitespace().any(|token| token == rule) {
+                self.preview.normalized.push(entry.clone());
+            }
+            self.preview.accepted.push(entry);
+        }
+    }
+}
+
+/// Parses `content` without changing anything. `existing` is the current block list,
+/// used to report duplicates.
+pub fn preview_import(content: &str, format: ImportFormat, existing: &[String]) -> ImportPreview {
+    let format = match format {
+        ImportFormat::Auto => detect_format(content),
+        format => format,
+    };
+    let mut collector = Collector {
+        preview: ImportPreview {
+            format,
+            ..Default::default()
+        },
+        existing: existing.iter().cloned().collect(),
+        seen: HashSet::new(),
+    };
+
+    if format == ImportFormat::Ublock && content.trim_start().starts_with('{') {
+        import_ublock_backup(content, &mut collector);
+        return collector.preview;
+    }
+
+    for (index, raw) in content.lines().enumerate() {
+        let line = raw.trim();
+        if format == ImportFormat::Leechblock {
+            if !is_leechblock_sites_key(line) {
+                continue;
+            }
+            let value = line.split_once('=').map(|(_, v)| v).unwrap_or_default();
+            for site in value.split_whitespace() {
+                collector.push(index + 1, site, parse_leechblock_site(site).map(|r| vec![r]));
+            }
+            continue;
+        }
+
+        if is_comment(line) {
+            continue;
+        }
+        let result = match format {
+            ImportFormat::Hosts => parse_hosts(line),
+            ImportFormat::Adblock | ImportFormat::Ublock => parse_adblock(line),
+            _ => parse_plain(line),
+        };
+        collector.push(index + 1, line, result);
+    }
+    collector.preview
+}
+
+fn import_ublock_backup(content: &str, collector: &mut Collector) {
+    let backup: serde_json::Value = match serde_json::from_str(content) {
+        Ok(value) => value,
+        Err(e) => {
+            collector.preview.rejected.push(RejectedLine {
+                line: 1,
+                text: String::new(),
+                reason: format!("invalid uBlock backup: {}", e),
+            });
+            return;
+        }
+    };
+
+    // 备份是单个 JSON，行号按各字段内部的行计
+    if let Some(filters) = backup.get("userFilters").and_then(|v| v.as_str()) {
+        for (index, raw) in filters.lines().enumerate() {
+            let line = raw.trim();
+            if !is_comment(line) {
+                collector.push(index + 1, line, parse_adblock(line));
+            }
+        }
+    }
+    if let Some(rules) = backup.get("dynamicFilteringString").and_then(|v| v.as_str()) {
+        for (index, raw) in rules.lines().enumerate() {
+            let line = raw.trim();
+            // 只关心屏蔽规则，其余动态规则（noop/allow）静默跳过
+            if line.ends_with(" block") {
+                collector.push(index + 1, line, parse_ublock_dynamic(line));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn preview_auto(content: &str) -> ImportPreview {
+        preview_import(content, ImportFormat::Auto, &[])
+    }
+
+    fn rejected(preview: &ImportPreview) -> Vec<(&str, &str)> {
+        preview
+            .rejected
+            .iter()
+            .map(|r| (r.text.as_str(), r.reason.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn detects_each_format() {
+        assert_eq!(detect_format("  {\"userFilters\": \"\"}"), ImportFormat::Ublock);
+        assert_eq!(detect_format("timedata1=0\nsites1=example.com"), ImportFormat::Leechblock);
+        assert_eq!(detect_format("[Adblock Plus 2.0]\nexample.com"), ImportFormat::Adblock);
+        assert_eq!(detect_format("# 127.0.0.1 comment\n::1 example.com"), ImportFormat::Hosts);
+        assert_eq!(detect_format("# 127.0.0.1 comment\nexample.com"), ImportFormat::Plain);
+        // sites 后面必须跟数字
+        assert_eq!(detect_format("sites=example.com"), ImportFormat::Plain);
+    }
+
+    #[test]
+    fn plain_strips_schemes_ports_and_comments() {
+        let content = "https://News.Example.com/**\nhttp://example.org:8080/\nexample.net/path\nsite.com # note\nexample.org\n";
+        let preview = preview_import(content, ImportFormat::Auto, &["site.com".to_string()]);
+
+        assert_eq!(preview.format, ImportFormat::Plain);
+        assert_eq!(preview.rules(), vec!["news.example.com/**", "example.org"]);
+        assert_eq!(preview.normalized.len(), 2);
+        assert_eq!(preview.duplicates.iter().map(|d| d.line).collect::<Vec<_>>(), vec![4, 5]);
+        assert_eq!(rejected(&preview), vec![("example.net/path", "path rules are not supported")]);
+    }
+
+    #[test]
+    fn hosts_skips_local_names_and_splits_multi_name_lines() {
+        let content = "\
+# comment
+127.0.0.1 localhost
+::1 ip6-localhost ip6-loopback
+0.0.0.0 0.0.0.0
+0.0.0.0 ads.example.com Tracker.example.com # inline comment
+127.0.0.1 localhost metrics.example.com
+example.com
+";
+        let preview = preview_auto(content);
+
+        assert_eq!(preview.format, ImportFormat::Hosts);
+        assert_eq!(preview.rules(), vec!["ads.example.com", "tracker.example.com", "metrics.example.com"]);
+        assert_eq!(preview.normalized.iter().map(|e| e.rule.as_str()).collect::<Vec<_>>(), vec!["tracker.example.com"]);
+        assert_eq!(
+            rejected(&preview),
+            vec![
+                ("127.0.0.1 localhost", "no blockable hostnames"),
+                ("::1 ip6-localhost ip6-loopback", "no blockable hostnames"),
+                ("0.0.0.0 0.0.0.0", "no blockable hostnames"),
+                ("example.com", "expected an IP address"),
+            ]
+        );
+    }
+
+    #[test]
+    fn adblock_accepts_only_domain_rules_with_harmless_options() {
+        let content = "\
+[Adblock Plus 2.0]
+! comment
+||ads.example.com^
+||cdn.example.com^$important,third-party
+||news.example.com^$~third-party
+||video.example.com^$script
+||example.com/banner^
+@@||allowed.example.com^
+example.com##.banner
+|https://tracker.example.org/|
+/banner/*
+";
+        let preview = preview_auto(content);
+
+        assert_eq!(preview.format, ImportFormat::Adblock);
+        assert_eq!(
+            preview.rules(),
+            vec!["ads.example.com/**", "cdn.example.com/**", "news.example.com/**", "tracker.example.org"]
+        );
+        assert_eq!(
+            rejected(&preview),
+            vec![
+                ("||video.example.com^$script", "unsupported filter option: script"),
+                ("||example.com/banner^", "path rules are not supported"),
+                ("@@||allowed.example.com^", "exception rules are not supported"),
+                ("example.com##.banner", "cosmetic filters are not supported"),
+                ("/banner/*", "only domain rules (||domain^) are supported"),
+            ]
+        );
+    }
+
+    #[test]
+    fn leechblock_widens_plain_sites_and_rejects_exceptions() {
+        let content = "timedata1=0\nsites1=example.com +allowed.example.com *.games.com https://news.com/** foo*.com\nsites2=\n";
+        let preview = preview_auto(content);
+
+        assert_eq!(preview.format, ImportFormat::Leechblock);
+        assert_eq!(preview.rules(), vec!["example.com/**", "*.games.com", "news.com/**"]);
+        assert!(preview.accepted.iter().all(|e| e.line == 2));
+        assert_eq!(
+            rejected(&preview),
+            vec![
+                ("+allowed.example.com", "allow exceptions are not supported"),
+                ("foo*.com", "only leading *. wildcards are supported"),
+            ]
+        );
+    }
+
+    #[test]
+    fn ublock_backup_reads_user_filters_and_global_dynamic_blocks() {
+        let content = r#"{
+            "userFilters": "! my filters\n||ads.example.com^\n@@||ok.example.com^",
+            "dynamicFilteringString": "* tracker.example.com * block\nbehind-the-scene * * noop\nexample.com social.example.com * block"
+        }"#;
+        let preview = preview_auto(content);
+
+        assert_eq!(preview.format, ImportFormat::Ublock);
+        assert_eq!(preview.rules(), vec!["ads.example.com/**", "tracker.example.com/**"]);
+        assert_eq!(
+            rejected(&preview),
+            vec![
+                ("@@||ok.example.com^", "exception rules are not supported"),
+                (
+                    "example.com social.example.com * block",
+                    "only global block rules (* host * block) are supported"
+                ),
+            ]
+        );
+
+        let broken = preview_auto("{ not json");
+        assert!(broken.accepted.is_empty());
+        assert_eq!(broken.rejected.len(), 1);
+        assert!(broken.rejected[0].reason.starts_with("invalid uBlock backup"));
+    }
+}
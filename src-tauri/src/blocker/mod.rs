@@ -1,5 +1,21 @@
-mod hosts;
-mod process;
+pub mod apps;
+pub mod budget;
+pub mod catalog;
+pub mod categories;
+pub mod dns;
+#[cfg(test)]
+pub mod fake;
+pub mod foreground;
+pub mod hosts;
+pub mod idle;
+pub mod import;
+pub mod process;
+pub mod reblock;
+pub mod rules;
+pub mod state;
+pub mod watch;
+pub mod website;
+pub mod worker;
 
 pub use hosts::*;
 pub use process::*;
@@ -1,99 +1,1025 @@
-use sysinfo::{ProcessExt, System, SystemExt};
-use std::sync::Mutex;
+use super::worker::{with_table, ProcessTable};
+use sysinfo::{Pid, Process, ProcessRefreshKind, ProcessStatus, System, UpdateKind, Users};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, Instant};
 use once_cell::sync::Lazy;
+use thiserror::Error;
 
-static SYSTEM: Lazy<Mutex<System>> = Lazy::new(|| {
-    let mut sys = System::new_all();
-    sys.refresh_all();
-    Mutex::new(sys)
-});
+pub const DEFAULT_GRACE_PERIOD_MS: u64 = 3000;
+const EXIT_POLL_INTERVAL: Duration = Duration::from_millis(100);
 
-#[derive(Debug, Clone)]
+static USER_ALLOWLIST: Lazy<Mutex<HashSet<String>>> = Lazy::new(|| Mutex::new(load_allowlist()));
+
+// 系统及桌面会话的关键进程，误杀会导致系统或登录会话崩溃
+#[cfg(target_os = "windows")]
+const SYSTEM_PROCESS_NAMES: &[&str] = &[
+    "system", "registry", "smss", "csrss", "wininit", "winlogon", "services",
+    "lsass", "lsaiso", "svchost", "fontdrvhost", "memory compression", "secure system",
+];
+
+#[cfg(target_os = "windows")]
+const SESSION_PROCESS_NAMES: &[&str] = &[
+    "explorer", "dwm", "sihost", "ctfmon", "taskhostw", "startmenuexperiencehost",
+    "shellexperiencehost", "runtimebroker", "conhost", "logonui",
+];
+
+#[cfg(target_os = "macos")]
+const SYSTEM_PROCESS_NAMES: &[&str] = &[
+    "kernel_task", "launchd", "logd", "configd", "opendirectoryd", "securityd",
+    "syslogd", "notifyd", "mds", "coreservicesd",
+];
+
+#[cfg(target_os = "macos")]
+const SESSION_PROCESS_NAMES: &[&str] = &[
+    "loginwindow", "windowserver", "dock", "finder", "systemuiserver", "controlcenter",
+];
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+const SYSTEM_PROCESS_NAMES: &[&str] = &[
+    "init", "systemd", "kthreadd", "systemd-journald", "systemd-logind", "systemd-udevd",
+    "dbus-daemon", "dbus-broker", "polkitd", "networkmanager", "sshd",
+];
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+const SESSION_PROCESS_NAMES: &[&str] = &[
+    "xorg", "xwayland", "gdm", "gdm-wayland-session", "gdm-x-session", "sddm", "lightdm",
+    "gnome-shell", "gnome-session-binary", "kwin_x11", "kwin_wayland", "plasmashell",
+    "ksmserver", "xfwm4", "xfce4-session", "mutter", "pipewire", "wireplumber", "pulseaudio",
+];
+
+#[derive(Error, Debug)]
+pub enum ProcessError {
+    #[error("Invalid glob pattern '{0}': {1}")]
+    InvalidGlob(String, glob::PatternError),
+    #[error("Invalid regex pattern '{0}': {1}")]
+    InvalidRegex(String, regex::Error),
+    #[error("Process {0} not found")]
+    NotFound(u32),
+    #[error("Refusing to kill protected process {name} ({pid}): {reason}")]
+    Protected {
+        pid: u32,
+        name: String,
+        reason: ProtectionReason,
+    },
+    #[error("Failed to kill process {0}")]
+    KillFailed(u32),
+    #[error("Failed to start process watch: {0}")]
+    WatchFailed(String),
+    #[error("Process worker unavailable: {0}")]
+    WorkerUnavailable(String),
+    #[error("Process worker crashed while handling the request")]
+    WorkerPanicked,
+    #[error("Process scan timed out after {0} ms")]
+    Timeout(u64),
+    #[error("Unknown application '{0}'")]
+    UnknownApp(String),
+    #[error("Failed to save protected allowlist: {0}")]
+    Config(std::io::Error),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProtectionReason {
+    SelfProcess,
+    Ancestor,
+    System,
+    Session,
+    Allowlisted,
+}
+
+impl std::fmt::Display for ProtectionReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let text = match self {
+            ProtectionReason::SelfProcess => "Focus Keeper itself",
+            ProtectionReason::Ancestor => "parent of Focus Keeper",
+            ProtectionReason::System => "system process",
+            ProtectionReason::Session => "desktop session process",
+            ProtectionReason::Allowlisted => "on the user allowlist",
+        };
+        f.write_str(text)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SkippedProcess {
+    pub pid: u32,
+    pub name: String,
+    pub reason: ProtectionReason,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TerminationOutcome {
+    /// Exited on its own within the grace period after SIGTERM / WM_CLOSE.
+    Terminated,
+    /// Still alive after the grace period and was force-killed.
+    ForceKilled,
+    /// Already gone before any signal was sent.
+    AlreadyExited,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TerminationResult {
+    pub pid: u32,
+    pub name: String,
+    pub outcome: TerminationOutcome,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TreeOrder {
+    /// Deepest descendants first, so launchers can't respawn children mid-kill.
+    #[default]
+    ChildrenFirst,
+    RootFirst,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TerminateOptions {
+    /// How long to wait after the polite close before force-killing. 0 kills immediately.
+    pub grace_period_ms: u64,
+    /// Also terminate every descendant of the matched processes.
+    pub kill_tree: bool,
+    pub tree_order: TreeOrder,
+}
+
+impl Default for TerminateOptions {
+    fn default() -> Self {
+        TerminateOptions {
+            grace_period_ms: DEFAULT_GRACE_PERIOD_MS,
+            kill_tree: true,
+            tree_order: TreeOrder::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct KillReport {
+    pub killed: u32,
+    pub skipped: Vec<SkippedProcess>,
+    pub results: Vec<TerminationResult>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProcessInfo {
     pub name: String,
     pub pid: u32,
     pub exe: Option<String>,
+    pub parent_pid: Option<u32>,
+    pub cmd: Vec<String>,
+    /// Unix timestamp (seconds) when the process started.
+    pub start_time: u64,
+    pub user: Option<String>,
+    /// Percentage of one CPU core since the previous refresh.
+    pub cpu_usage: f32,
+    pub memory_bytes: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchMode {
+    /// Process name equals the pattern (case-insensitive, `.exe` suffix optional).
+    #[default]
+    Exact,
+    /// Full executable path equals the pattern.
+    ExePath,
+    /// Shell-style glob against the process name, e.g. `steam*`.
+    Glob,
+    /// Regular expression against the process name.
+    Regex,
+    /// Joined command line contains the pattern.
+    CmdlineContains,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProcessMatcher {
+    #[serde(default)]
+    pub mode: MatchMode,
+    pub pattern: String,
+}
+
+enum CompiledPattern {
+    Exact(String),
+    ExePath(String),
+    Glob(glob::Pattern),
+    Regex(regex::Regex),
+    CmdlineContains(String),
+}
+
+pub struct CompiledMatcher {
+    pattern: CompiledPattern,
+}
+
+impl ProcessMatcher {
+    pub fn new(mode: MatchMode, pattern: impl Into<String>) -> Self {
+        ProcessMatcher {
+            mode,
+            pattern: pattern.into(),
+        }
+    }
+
+    pub fn exact(name: impl Into<String>) -> Self {
+        Self::new(MatchMode::Exact, name)
+    }
+
+    pub fn compile(&self) -> Result<CompiledMatcher, ProcessError> {
+        let pattern = match self.mode {
+            MatchMode::Exact => CompiledPattern::Exact(normalize_name(&self.pattern)),
+            MatchMode::ExePath => CompiledPattern::ExePath(normalize_path(&self.pattern)),
+            MatchMode::Glob => glob::Pattern::new(&self.pattern)
+                .map(CompiledPattern::Glob)
+                .map_err(|e| ProcessError::InvalidGlob(self.pattern.clone(), e))?,
+            MatchMode::Regex => regex::RegexBuilder::new(&self.pattern)
+                .case_insensitive(true)
+                .build()
+                .map(CompiledPattern::Regex)
+                .map_err(|e| ProcessError::InvalidRegex(self.pattern.clone(), e))?,
+            MatchMode::CmdlineContains => {
+                CompiledPattern::CmdlineContains(self.pattern.to_lowercase())
+            }
+        };
+
+        Ok(CompiledMatcher { pattern })
+    }
+}
+
+impl CompiledMatcher {
+    pub fn matches(&self, process: &Process) -> bool {
+        let exe = process.exe().map(|exe| exe.to_string_lossy());
+        self.matches_fields(process.name(), exe.as_deref(), process.cmd())
+    }
+
+    pub fn matches_info(&self, process: &ProcessInfo) -> bool {
+        self.matches_fields(&process.name, process.exe.as_deref(), &process.cmd)
+    }
+
+    fn matches_fields(&self, name: &str, exe: Option<&str>, cmd: &[String]) -> bool {
+        match &self.pattern {
+            CompiledPattern::Exact(expected) => normalize_name(name) == *expected,
+            CompiledPattern::ExePath(path) => exe
+                .map(|exe| normalize_path(exe) == *path)
+                .unwrap_or(false),
+            CompiledPattern::Glob(pattern) => {
+                let options = glob::MatchOptions {
+                    case_sensitive: false,
+                    ..glob::MatchOptions::new()
+                };
+                pattern.matches_with(name, options)
+            }
+            CompiledPattern::Regex(regex) => regex.is_match(name),
+            CompiledPattern::CmdlineContains(needle) => {
+                cmd.join(" ").to_lowercase().contains(needle)
+            }
+        }
+    }
+}
+
+fn normalize_name(name: &str) -> String {
+    let lower = name.trim().to_lowercase();
+    match lower.strip_suffix(".exe") {
+        Some(stripped) => stripped.to_string(),
+        None => lower,
+    }
+}
+
+fn normalize_path(path: &str) -> String {
+    let path = Path::new(path.trim()).to_string_lossy().to_string();
+    if cfg!(windows) {
+        path.replace('/', "\\").to_lowercase()
+    } else {
+        path
+    }
+}
+
+fn to_process_info(pid: sysinfo::Pid, process: &Process, users: &Users) -> ProcessInfo {
+    let user = process
+        .user_id()
+        .and_then(|uid| users.get_user_by_id(uid))
+        .map(|u| u.name().to_string());
+
+    ProcessInfo {
+        name: process.name().to_string(),
+        pid: pid.as_u32(),
+        exe: process.exe().map(|p| p.to_string_lossy().to_string()),
+        parent_pid: process.parent().map(|p| p.as_u32()),
+        cmd: process.cmd().to_vec(),
+        start_time: process.start_time(),
+        user,
+        cpu_usage: process.cpu_usage(),
+        memory_bytes: process.memory(),
+    }
+}
+
+fn lock_allowlist() -> MutexGuard<'static, HashSet<String>> {
+    // 名单只是字符串集合，即使持锁线程 panic 也不会处于不一致状态
+    USER_ALLOWLIST.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+pub fn refresh_system() -> Result<(), ProcessError> {
+    with_table(|table| {
+        table.sys.refresh_processes();
+        table.users.refresh_list();
+    })
+}
+
+/// Compiled matchers used by the watch: a process is a hit when it matches one of
+/// `targets` and none of `exempt`.
+#[derive(Default)]
+pub struct MatchSet {
+    pub targets: Vec<CompiledMatcher>,
+    pub exempt: Vec<CompiledMatcher>,
+    /// Only hit interactive desktop apps of the current user, see `is_user_gui_app`.
+    pub gui_apps_only: bool,
+}
+
+impl MatchSet {
+    pub fn hit(&self, sys: &System, pid: Pid, process: &Process) -> Option<usize> {
+        let index = self.targets.iter().position(|m| m.matches(process))?;
+        if self.exempt.iter().any(|m| m.matches(process)) {
+            return None;
+        }
+        if self.gui_apps_only && !is_user_gui_app(sys, pid, process) {
+            return None;
+        }
+        Some(index)
+    }
 }
 
-pub fn refresh_system() {
-    let mut sys = SYSTEM.lock().unwrap();
-    sys.refresh_all();
+/// Whether `process` is a desktop app the current user started, as opposed to a
+/// shell, interpreter, console tool or background service.
+fn is_user_gui_app(sys: &System, pid: Pid, process: &Process) -> bool {
+    let own_user = sysinfo::get_current_pid()
+        .ok()
+        .and_then(|own| sys.process(own))
+        .and_then(|own| own.user_id());
+    if own_user.is_none() || process.user_id() != own_user {
+        return false;
+    }
+    has_gui_subsystem(pid, process)
+}
+
+// Windows 上看可执行文件的 PE 子系统：控制台程序（cmd、python、各类 CLI）不算
+#[cfg(target_os = "windows")]
+fn has_gui_subsystem(_pid: Pid, process: &Process) -> bool {
+    use std::io::{Read, Seek, SeekFrom};
+
+    const IMAGE_SUBSYSTEM_WINDOWS_GUI: u16 = 2;
+    let read = |exe: &Path| -> std::io::Result<u16> {
+        let mut file = std::fs::File::open(exe)?;
+        let mut word = [0u8; 4];
+        file.seek(SeekFrom::Start(0x3c))?;
+        file.read_exact(&mut word)?;
+        // PE 签名(4) + COFF 头(20) 之后是可选头，Subsystem 位于其偏移 68 处
+        let pe_offset = u32::from_le_bytes(word) as u64;
+        file.seek(SeekFrom::Start(pe_offset))?;
+        file.read_exact(&mut word)?;
+        if &word != b"PE\0\0" {
+            return Ok(0);
+        }
+        file.seek(SeekFrom::Start(pe_offset + 24 + 68))?;
+        let mut subsystem = [0u8; 2];
+        file.read_exact(&mut subsystem)?;
+        Ok(u16::from_le_bytes(subsystem))
+    };
+
+    let Some(exe) = process.exe() else {
+        return false;
+    };
+    let in_windows_dir = std::env::var("SystemRoot")
+        .map(|root| exe.starts_with(root))
+        .unwrap_or(false);
+    !in_windows_dir && read(exe).map(|s| s == IMAGE_SUBSYSTEM_WINDOWS_GUI).unwrap_or(false)
+}
+
+// macOS 上只有 .app 包里的主程序才是图形应用，系统自带的除外
+#[cfg(target_os = "macos")]
+fn has_gui_subsystem(_pid: Pid, process: &Process) -> bool {
+    process.exe().is_some_and(|exe| {
+        let path = exe.to_string_lossy();
+        path.contains(".app/Contents/MacOS/") && !path.starts_with("/System/")
+    })
+}
+
+// Linux 上要求进程连接了图形会话且没有控制终端，终端里的 shell、解释器和后台服务都不算
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn has_gui_subsystem(pid: Pid, process: &Process) -> bool {
+    let graphical = process
+        .environ()
+        .iter()
+        .any(|var| var.starts_with("DISPLAY=") || var.starts_with("WAYLAND_DISPLAY="));
+    if !graphical {
+        return false;
+    }
+    // /proc/<pid>/stat 第 7 个字段是 tty_nr；comm 可能含空格，从最后一个 ')' 之后开始数
+    std::fs::read_to_string(format!("/proc/{}/stat", pid.as_u32()))
+        .ok()
+        .and_then(|stat| {
+            let rest = &stat[stat.rfind(')')? + 1..];
+            rest.split_whitespace().nth(4)?.parse::<i64>().ok()
+        })
+        == Some(0)
+}
+
+#[derive(Debug, Default)]
+pub struct ProcessDiff {
+    pub started: Vec<ProcessInfo>,
+    pub exited: Vec<ProcessInfo>,
+    /// Newly seen processes that hit the match set, with the target matcher's index.
+    pub matched: Vec<(usize, ProcessInfo)>,
+}
+
+/// Refreshes only the process list (no CPU, memory or disk sampling) and diffs it
+/// against `known`, which is updated in place. PIDs are paired with their start
+/// time so a reused PID shows up as an exit plus a start.
+pub fn poll_process_changes(
+    known: &mut HashMap<u32, ProcessInfo>,
+    matchers: Arc<MatchSet>,
+) -> Result<ProcessDiff, ProcessError> {
+    let mut snapshot = std::mem::take(known);
+
+    let (snapshot, diff) = with_table(move |table| {
+        table.sys.refresh_processes_specifics(
+            ProcessRefreshKind::new()
+                .with_exe(UpdateKind::OnlyIfNotSet)
+                .with_cmd(UpdateKind::OnlyIfNotSet)
+                .with_user(UpdateKind::OnlyIfNotSet)
+                .with_environ(UpdateKind::OnlyIfNotSet),
+        );
+
+        let mut diff = ProcessDiff::default();
+        let mut alive = HashSet::new();
+
+        for (pid, process) in table.sys.processes() {
+            let id = pid.as_u32();
+            alive.insert(id);
+
+            let is_new = match snapshot.get(&id) {
+                Some(info) => info.start_time != process.start_time(),
+                None => true,
+            };
+            if !is_new {
+                continue;
+            }
+
+            if let Some(old) = snapshot.remove(&id) {
+                diff.exited.push(old);
+            }
+
+            let info = to_process_info(*pid, process, &table.users);
+            if let Some(index) = matchers.hit(&table.sys, *pid, process) {
+                diff.matched.push((index, info.clone()));
+            }
+            snapshot.insert(id, info.clone());
+            diff.started.push(info);
+        }
+
+        let gone: Vec<u32> = snapshot.keys().filter(|pid| !alive.contains(pid)).copied().collect();
+        for pid in gone {
+            if let Some(info) = snapshot.remove(&pid) {
+                diff.exited.push(info);
+            }
+        }
+
+        (snapshot, diff)
+    })?;
+
+    *known = snapshot;
+    Ok(diff)
+}
+
+/// Refreshes the process list once and reports, for each group of matchers,
+/// whether any running process matches it.
+pub fn sample_running(groups: &[Vec<ProcessMatcher>]) -> Result<Vec<bool>, ProcessError> {
+    let compiled = groups
+        .iter()
+        .map(|group| compile_group(group))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    with_table(move |table| {
+        table.sys.refresh_processes_specifics(
+            ProcessRefreshKind::new()
+                .with_exe(UpdateKind::OnlyIfNotSet)
+                .with_cmd(UpdateKind::OnlyIfNotSet),
+        );
+        compiled
+            .iter()
+            .map(|group| {
+                table
+                    .sys
+                    .processes()
+                    .values()
+                    .any(|process| group.iter().any(|m| m.matches(process)))
+            })
+            .collect()
+    })
+}
+
+fn compile_group(group: &[ProcessMatcher]) -> Result<Vec<CompiledMatcher>, ProcessError> {
+    group.iter().map(|m| m.compile()).collect()
+}
+
+pub fn find_processes(matcher: &ProcessMatcher) -> Result<Vec<ProcessInfo>, ProcessError> {
+    let compiled = matcher.compile()?;
+
+    with_table(move |table| {
+        table
+            .sys
+            .processes()
+            .iter()
+            .filter(|(_, process)| compiled.matches(process))
+            .map(|(pid, process)| to_process_info(*pid, process, &table.users))
+            .collect()
+    })
 }
 
 pub fn find_processes_by_name(name: &str) -> Vec<ProcessInfo> {
-    let sys = SYSTEM.lock().unwrap();
-    let mut processes = Vec::new();
+    find_processes(&ProcessMatcher::exact(name)).unwrap_or_default()
+}
 
-    let name_lower = name.to_lowercase();
+/// Refreshes and returns a single process, or `None` if it has exited.
+pub fn get_process_info(pid: u32) -> Result<Option<ProcessInfo>, ProcessError> {
+    with_table(move |table| {
+        let pid = Pid::from_u32(pid);
+        if !table.sys.refresh_process(pid) {
+            return None;
+        }
+        table
+            .sys
+            .process(pid)
+            .map(|process| to_process_info(pid, process, &table.users))
+    })
+}
 
-    for (pid, process) in sys.processes() {
-        let process_name = process.name().to_lowercase();
-        if process_name.contains(&name_lower) || process_name == name_lower {
-            processes.push(ProcessInfo {
-                name: process.name().to_string(),
-                pid: pid.as_u32(),
-                exe: process.exe().map(|p| p.to_string_lossy().to_string()),
-            });
+// Focus Keeper 自己启动的子进程（如 WebView）同样受保护
+fn is_descendant_of(sys: &System, pid: Pid, ancestor: Pid) -> bool {
+    let mut current = sys.process(pid).and_then(|p| p.parent());
+    let mut visited = HashSet::new();
+    while let Some(parent) = current {
+        if parent == ancestor {
+            return true;
         }
+        if !visited.insert(parent) {
+            break;
+        }
+        current = sys.process(parent).and_then(|p| p.parent());
+    }
+    false
+}
+
+fn protection_reason(sys: &System, pid: Pid, process: &Process) -> Option<ProtectionReason> {
+    let own_pid = Pid::from_u32(std::process::id());
+    if pid == own_pid || is_descendant_of(sys, pid, own_pid) {
+        return Some(ProtectionReason::SelfProcess);
+    }
+
+    let mut ancestor = sys.process(own_pid).and_then(|p| p.parent());
+    let mut visited = HashSet::new();
+    while let Some(parent) = ancestor {
+        if parent == pid {
+            return Some(ProtectionReason::Ancestor);
+        }
+        if !visited.insert(parent) {
+            break;
+        }
+        ancestor = sys.process(parent).and_then(|p| p.parent());
+    }
+
+    let name = normalize_name(process.name());
+    // PID 0/1 是 idle/init，Linux 的 2 是 kthreadd，Windows 的 4 是 System
+    if pid.as_u32() <= 2 || (cfg!(windows) && pid.as_u32() == 4) {
+        return Some(ProtectionReason::System);
+    }
+    if SYSTEM_PROCESS_NAMES.contains(&name.as_str()) {
+        return Some(ProtectionReason::System);
+    }
+    if SESSION_PROCESS_NAMES.contains(&name.as_str()) {
+        return Some(ProtectionReason::Session);
+    }
+
+    if lock_allowlist().contains(&name) {
+        return Some(ProtectionReason::Allowlisted);
+    }
+
+    None
+}
+
+pub fn get_protected_allowlist() -> Vec<String> {
+    let mut names: Vec<String> = lock_allowlist().iter().cloned().collect();
+    names.sort();
+    names
+}
+
+fn get_allowlist_path() -> PathBuf {
+    let path = dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("FocusKeeper")
+        .join("process-allowlist.json");
+
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
     }
+    path
+}
+
+fn normalize_allowlist(names: &[String]) -> HashSet<String> {
+    names
+        .iter()
+        .map(|name| normalize_name(name))
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+fn load_allowlist() -> HashSet<String> {
+    fs::read_to_string(get_allowlist_path())
+        .ok()
+        .and_then(|content| serde_json::from_str::<Vec<String>>(&content).ok())
+        .map(|names| normalize_allowlist(&names))
+        .unwrap_or_default()
+}
+
+/// Whether `names` would protect a process the current allowlist does not.
+pub fn loosens_protected_allowlist(names: &[String]) -> bool {
+    let current = lock_allowlist();
+    normalize_allowlist(names).iter().any(|name| !current.contains(name))
+}
+
+pub fn set_protected_allowlist(names: &[String]) -> Result<(), ProcessError> {
+    let names = normalize_allowlist(names);
+    let mut sorted: Vec<&String> = names.iter().collect();
+    sorted.sort();
+    let content = serde_json::to_string_pretty(&sorted).map_err(|e| ProcessError::Config(e.into()))?;
+    fs::write(get_allowlist_path(), content).map_err(ProcessError::Config)?;
+    *lock_allowlist() = names;
+    Ok(())
+}
 
-    processes
+#[cfg(target_os = "windows")]
+fn send_polite_close(pid: Pid, _process: &Process) -> bool {
+    use std::process::Command;
+    // 不带 /F 的 taskkill 会向窗口发送 WM_CLOSE，让程序有机会保存
+    Command::new("taskkill")
+        .args(["/PID", &pid.as_u32().to_string()])
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false)
 }
 
-pub fn kill_process(pid: u32) -> bool {
-    let mut sys = SYSTEM.lock().unwrap();
-    if let Some(process) = sys.process(sysinfo::Pid::from_u32(pid)) {
-        process.kill()
+#[cfg(not(target_os = "windows"))]
+fn send_polite_close(_pid: Pid, process: &Process) -> bool {
+    process.kill_with(sysinfo::Signal::Term).unwrap_or(false)
+}
+
+fn has_exited(sys: &mut System, pid: Pid) -> bool {
+    if !sys.refresh_process(pid) {
+        return true;
+    }
+    match sys.process(pid) {
+        Some(process) => matches!(process.status(), ProcessStatus::Zombie | ProcessStatus::Dead),
+        None => true,
+    }
+}
+
+fn force_kill(table: &mut ProcessTable, target: &ProcessInfo) -> TerminationResult {
+    let pid = Pid::from_u32(target.pid);
+    let outcome = if has_exited(&mut table.sys, pid) {
+        TerminationOutcome::Terminated
     } else {
-        false
+        match table.sys.process(pid) {
+            Some(process) if process.kill() => TerminationOutcome::ForceKilled,
+            _ => TerminationOutcome::Failed,
+        }
+    };
+
+    TerminationResult {
+        pid: target.pid,
+        name: target.name.clone(),
+        outcome,
     }
 }
 
-pub fn kill_processes_by_name(name: &str) -> u32 {
-    let processes = find_processes_by_name(name);
-    let mut killed_count = 0u32;
+/// Politely closes every target, waits up to the grace period for them to exit,
+/// then force-kills whatever is left. Protected processes are skipped.
+///
+/// Each step is a separate worker job; the waiting happens on the calling
+/// thread so other process queries aren't blocked for the grace period.
+pub fn terminate_processes(targets: &[ProcessInfo], options: &TerminateOptions) -> Result<KillReport, ProcessError> {
+    let targets = targets.to_vec();
+    let grace_period_ms = options.grace_period_ms;
+
+    let (mut report, mut pending) = with_table(move |table| {
+        let mut report = KillReport::default();
+        let mut pending = Vec::new();
+
+        for target in targets {
+            if table.is_cancelled() {
+                break;
+            }
+            let pid = Pid::from_u32(target.pid);
+            let process = match table.sys.process(pid) {
+                Some(process) => process,
+                None => {
+                    report.results.push(TerminationResult {
+                        pid: target.pid,
+                        name: target.name.clone(),
+                        outcome: TerminationOutcome::AlreadyExited,
+                    });
+                    continue;
+                }
+            };
+
+            if let Some(reason) = protection_reason(&table.sys, pid, process) {
+                report.skipped.push(SkippedProcess {
+                    pid: target.pid,
+                    name: target.name.clone(),
+                    reason,
+                });
+                continue;
+            }
+
+            if grace_period_ms > 0 && send_polite_close(pid, process) {
+                pending.push(target);
+                continue;
+            }
 
-    for process in processes {
-        if kill_process(process.pid) {
-            killed_count += 1;
+            // 无法礼貌关闭（如无窗口的进程）或未设置宽限期时直接强制结束
+            report.results.push(force_kill(table, &target));
         }
+
+        (report, pending)
+    })?;
+
+    let deadline = Instant::now() + Duration::from_millis(grace_period_ms);
+    while !pending.is_empty() && Instant::now() < deadline {
+        thread::sleep(EXIT_POLL_INTERVAL);
+
+        let (exited, still_running) = with_table(move |table| {
+            pending
+                .into_iter()
+                .partition::<Vec<_>, _>(|target| has_exited(&mut table.sys, Pid::from_u32(target.pid)))
+        })?;
+
+        report.results.extend(exited.into_iter().map(|target| TerminationResult {
+            pid: target.pid,
+            name: target.name,
+            outcome: TerminationOutcome::Terminated,
+        }));
+        pending = still_running;
     }
 
-    killed_count
+    if !pending.is_empty() {
+        let forced = with_table(move |table| {
+            let mut forced = Vec::new();
+            for target in &pending {
+                if table.is_cancelled() {
+                    break;
+                }
+                forced.push(force_kill(table, target));
+            }
+            forced
+        })?;
+        report.results.extend(forced);
+    }
+
+    report.killed = count_killed(&report.results);
+    Ok(report)
 }
 
-pub fn get_running_apps(app_names: &[String]) -> Vec<ProcessInfo> {
-    refresh_system();
-    let mut running = Vec::new();
+fn count_killed(results: &[TerminationResult]) -> u32 {
+    results
+        .iter()
+        .filter(|r| matches!(r.outcome, TerminationOutcome::Terminated | TerminationOutcome::ForceKilled))
+        .count() as u32
+}
+
+/// Expands the given roots to include all their descendants, using the parent
+/// PIDs from the current snapshot. Each process appears once, in the requested order.
+/// Protected processes are left out together with everything below them.
+pub fn expand_process_tree(roots: &[ProcessInfo], order: TreeOrder) -> Result<Vec<ProcessInfo>, ProcessError> {
+    let roots = roots.to_vec();
+
+    with_table(move |table| {
+        let mut children: HashMap<Pid, Vec<Pid>> = HashMap::new();
+        for (pid, process) in table.sys.processes() {
+            if let Some(parent) = process.parent() {
+                children.entry(parent).or_default().push(*pid);
+            }
+        }
+
+        let mut seen = HashSet::new();
+        let mut tree = Vec::new();
+        let mut queue: VecDeque<Pid> = roots.iter().map(|p| Pid::from_u32(p.pid)).collect();
+
+        while let Some(pid) = queue.pop_front() {
+            if !seen.insert(pid) {
+                continue;
+            }
+            match table.sys.process(pid) {
+                // 受保护的进程（如会话进程、Focus Keeper 自身）连同其子树都不展开
+                Some(process) if protection_reason(&table.sys, pid, process).is_some() => continue,
+                Some(process) => tree.push(to_process_info(pid, process, &table.users)),
+                None => {
+                    if let Some(root) = roots.iter().find(|p| p.pid == pid.as_u32()) {
+                        tree.push(root.clone());
+                    }
+                }
+            }
+            if let Some(kids) = children.get(&pid) {
+                queue.extend(kids.iter().copied());
+            }
+        }
 
-    for app_name in app_names {
-        let processes = find_processes_by_name(app_name);
-        running.extend(processes);
+        // 广度优先得到的顺序是由根到叶，反转即为先子后父
+        if order == TreeOrder::ChildrenFirst {
+            tree.reverse();
+        }
+
+        tree
+    })
+}
+
+/// Splits `roots` into processes that may be terminated and protected ones.
+fn screen_roots(roots: &[ProcessInfo]) -> Result<(Vec<ProcessInfo>, Vec<SkippedProcess>), ProcessError> {
+    let roots = roots.to_vec();
+    with_table(move |table| {
+        let mut allowed = Vec::new();
+        let mut skipped = Vec::new();
+        for root in roots {
+            let pid = Pid::from_u32(root.pid);
+            match table.sys.process(pid).and_then(|process| protection_reason(&table.sys, pid, process)) {
+                Some(reason) => skipped.push(SkippedProcess {
+                    pid: root.pid,
+                    name: root.name,
+                    reason,
+                }),
+                None => allowed.push(root),
+            }
+        }
+        (allowed, skipped)
+    })
+}
+
+/// Terminates `roots` and, with `kill_tree`, their descendants. Protected roots are
+/// refused before their tree is looked at, so nothing below them is touched.
+pub fn terminate_trees(roots: &[ProcessInfo], options: &TerminateOptions) -> Result<KillReport, ProcessError> {
+    let (allowed, skipped) = screen_roots(roots)?;
+    let targets = if options.kill_tree && !allowed.is_empty() {
+        expand_process_tree(&allowed, options.tree_order)?
+    } else {
+        allowed
+    };
+
+    let mut report = if targets.is_empty() {
+        KillReport::default()
+    } else {
+        terminate_processes(&targets, options)?
+    };
+    report.skipped.splice(0..0, skipped);
+    Ok(report)
+}
+
+/// Like `terminate_trees`, but reports per root. All trees are terminated as one
+/// batch, so the grace period is waited out once rather than once per root.
+pub fn terminate_each_tree(roots: &[ProcessInfo], options: &TerminateOptions) -> Result<Vec<KillReport>, ProcessError> {
+    let (allowed, skipped) = screen_roots(roots)?;
+    let mut trees = Vec::with_capacity(allowed.len());
+    for root in &allowed {
+        trees.push(if options.kill_tree {
+            expand_process_tree(std::slice::from_ref(root), options.tree_order)?
+        } else {
+            vec![root.clone()]
+        });
     }
 
-    running
+    let mut seen = HashSet::new();
+    let targets: Vec<ProcessInfo> = trees
+        .iter()
+        .flatten()
+        .filter(|p| seen.insert(p.pid))
+        .cloned()
+        .collect();
+    let batch = if targets.is_empty() {
+        KillReport::default()
+    } else {
+        terminate_processes(&targets, options)?
+    };
+
+    Ok(roots
+        .iter()
+        .map(|root| {
+            let mut report = KillReport::default();
+            if let Some(skip) = skipped.iter().find(|s| s.pid == root.pid) {
+                report.skipped.push(skip.clone());
+                return report;
+            }
+            let members: HashSet<u32> = allowed
+                .iter()
+                .position(|a| a.pid == root.pid)
+                .map(|index| trees[index].iter().map(|p| p.pid).collect())
+                .unwrap_or_default();
+            report.results = batch.results.iter().filter(|r| members.contains(&r.pid)).cloned().collect();
+            report.skipped = batch.skipped.iter().filter(|s| members.contains(&s.pid)).cloned().collect();
+            report.killed = count_killed(&report.results);
+            report
+        })
+        .collect())
+}
+
+pub fn kill_process(pid: u32, options: &TerminateOptions) -> Result<KillReport, ProcessError> {
+    let (target, protection) = with_table(move |table| {
+        let sys_pid = Pid::from_u32(pid);
+        table.sys.process(sys_pid).map(|process| {
+            (
+                to_process_info(sys_pid, process, &table.users),
+                protection_reason(&table.sys, sys_pid, process),
+            )
+        })
+    })?
+    .ok_or(ProcessError::NotFound(pid))?;
+
+    if let Some(reason) = protection {
+        return Err(ProcessError::Protected {
+            pid,
+            name: target.name,
+            reason,
+        });
+    }
+
+    let report = terminate_trees(&[target], options)?;
+    if let Some(skipped) = report.skipped.iter().find(|s| s.pid == pid) {
+        return Err(ProcessError::Protected {
+            pid: skipped.pid,
+            name: skipped.name.clone(),
+            reason: skipped.reason,
+        });
+    }
+
+    match report.results.iter().find(|r| r.pid == pid) {
+        Some(result) if result.outcome == TerminationOutcome::Failed => {
+            Err(ProcessError::KillFailed(pid))
+        }
+        Some(_) => Ok(report),
+        None => Err(ProcessError::KillFailed(pid)),
+    }
 }
 
-pub fn is_app_running(name: &str) -> bool {
-    !find_processes_by_name(name).is_empty()
+pub fn kill_processes(matcher: &ProcessMatcher, options: &TerminateOptions) -> Result<KillReport, ProcessError> {
+    kill_matching(std::slice::from_ref(matcher), options)
 }
 
-pub fn get_all_running_processes() -> Vec<ProcessInfo> {
-    let sys = SYSTEM.lock().unwrap();
+/// Terminates everything matched by any of `matchers` as a single operation, so
+/// the grace period is waited out once rather than per matcher.
+pub fn kill_matching(matchers: &[ProcessMatcher], options: &TerminateOptions) -> Result<KillReport, ProcessError> {
     let mut processes = Vec::new();
+    for matcher in matchers {
+        processes.extend(find_processes(matcher)?);
+    }
+    processes.sort_by_key(|p| p.pid);
+    processes.dedup_by_key(|p| p.pid);
+    terminate_trees(&processes, options)
+}
 
-    for (pid, process) in sys.processes() {
-        processes.push(ProcessInfo {
-            name: process.name().to_string(),
-            pid: pid.as_u32(),
-            exe: process.exe().map(|p| p.to_string_lossy().to_string()),
-        });
+pub fn kill_processes_by_name(name: &str) -> u32 {
+    kill_processes(&ProcessMatcher::exact(name), &TerminateOptions::default())
+        .map(|report| report.killed)
+        .unwrap_or(0)
+}
+
+pub fn get_running_apps(matchers: &[ProcessMatcher]) -> Result<Vec<ProcessInfo>, ProcessError> {
+    refresh_system()?;
+    let mut running = Vec::new();
+
+    for matcher in matchers {
+        running.extend(find_processes(matcher)?);
     }
 
-    processes
+    running.sort_by_key(|p| p.pid);
+    running.dedup_by_key(|p| p.pid);
+
+    Ok(running)
+}
+
+pub fn is_app_running(matcher: &ProcessMatcher) -> Result<bool, ProcessError> {
+    Ok(!find_processes(matcher)?.is_empty())
+}
+
+pub fn get_all_running_processes() -> Result<Vec<ProcessInfo>, ProcessError> {
+    with_table(|table| {
+        table
+            .sys
+            .processes()
+            .iter()
+            .map(|(pid, process)| to_process_info(*pid, process, &table.users))
+            .collect()
+    })
 }
 
-pub fn get_process_count() -> usize {
-    let sys = SYSTEM.lock().unwrap();
-    sys.processes().len()
+pub fn get_process_count() -> Result<usize, ProcessError> {
+    with_table(|table| table.sys.processes().len())
 }
@@ -0,0 +1,88 @@
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const MAX_TEMPORARY_UNBLOCK_MINUTES: u64 = 120;
+
+/// A rule that was temporarily unblocked and must be blocked again at `reblock_at`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PendingReblock {
+    pub rule: String,
+    /// Unix timestamp (seconds).
+    pub reblock_at: u64,
+}
+
+static PENDING: Lazy<Mutex<Vec<PendingReblock>>> = Lazy::new(|| Mutex::new(load_pending()));
+
+fn lock_pending() -> std::sync::MutexGuard<'static, Vec<PendingReblock>> {
+    PENDING.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn get_pending_path() -> PathBuf {
+    let path = dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("FocusKeeper")
+        .join("pending-reblocks.json");
+
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    path
+}
+
+fn load_pending() -> Vec<PendingReblock> {
+    fs::read_to_string(get_pending_path())
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save_pending(pending: &[PendingReblock]) -> std::io::Result<()> {
+    let content = serde_json::to_string_pretty(pending)?;
+    fs::write(get_pending_path(), content)
+}
+
+pub fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Records a pending re-block, replacing any earlier one for the same rule.
+/// Must be persisted before the rule is removed, so a crash can't lose it.
+pub fn schedule_reblock(rule: &str, minutes: u64) -> std::io::Result<PendingReblock> {
+    let entry = PendingReblock {
+        rule: rule.to_string(),
+        reblock_at: now_secs() + minutes * 60,
+    };
+    let mut pending = lock_pending();
+    pending.retain(|p| p.rule != rule);
+    pending.push(entry.clone());
+    save_pending(&pending)?;
+    Ok(entry)
+}
+
+pub fn get_pending_reblocks() -> Vec<PendingReblock> {
+    lock_pending().clone()
+}
+
+pub fn find_pending(rule: &str) -> Option<PendingReblock> {
+    lock_pending().iter().find(|p| p.rule == rule).cloned()
+}
+
+/// Removes `entry` once it has been re-blocked. Returns false if it was replaced
+/// or cancelled in the meantime.
+pub fn complete_reblock(entry: &PendingReblock) -> std::io::Result<bool> {
+    let mut pending = lock_pending();
+    let before = pending.len();
+    pending.retain(|p| p != entry);
+    if pending.len() == before {
+        return Ok(false);
+    }
+    save_pending(&pending)?;
+    Ok(true)
+}
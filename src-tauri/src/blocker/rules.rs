@@ -0,0 +1,97 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleKind {
+    /// `example.com` — only the name itself.
+    Exact,
+    /// `*.example.com` — any subdomain, but not `example.com` itself.
+    Subdomains,
+    /// `example.com/**` or `.example.com` — the name and all of its subdomains.
+    Suffix,
+}
+
+/// A website blocking rule as written by the user.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct DomainRule {
+    pub kind: RuleKind,
+    pub domain: String,
+}
+
+pub fn normalize_domain(domain: &str) -> String {
+    domain.trim().trim_end_matches('.').to_lowercase()
+}
+
+/// Whether `name` looks like a concrete hostname (not an IP address or a bare label).
+pub fn is_hostname(name: &str) -> bool {
+    is_valid_domain(name)
+        && name.contains('.')
+        && name
+            .rsplit('.')
+            .next()
+            .map(|tld| tld.chars().any(|c| c.is_ascii_alphabetic()))
+            .unwrap_or(false)
+}
+
+fn is_valid_domain(domain: &str) -> bool {
+    !domain.is_empty()
+        && domain.len() <= 253
+        && domain.split('.').all(|label| {
+            !label.is_empty()
+                && label.len() <= 63
+                && label
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        })
+}
+
+impl DomainRule {
+    pub fn parse(rule: &str) -> Option<Self> {
+        let rule = normalize_domain(rule);
+        let (kind, domain) = if let Some(domain) = rule.strip_prefix("*.") {
+            (RuleKind::Subdomains, domain)
+        } else if let Some(domain) = rule.strip_suffix("/**") {
+            (RuleKind::Suffix, domain)
+        } else if let Some(domain) = rule.strip_prefix('.') {
+            (RuleKind::Suffix, domain)
+        } else {
+            (RuleKind::Exact, rule.as_str())
+        };
+
+        if !is_valid_domain(domain) {
+            return None;
+        }
+        Some(DomainRule {
+            kind,
+            domain: domain.to_string(),
+        })
+    }
+
+    pub fn is_wildcard(&self) -> bool {
+        self.kind != RuleKind::Exact
+    }
+
+    pub fn matches(&self, name: &str) -> bool {
+        let name = normalize_domain(name);
+        let is_subdomain = name.len() > self.domain.len()
+            && name.ends_with(self.domain.as_str())
+            && name.as_bytes()[name.len() - self.domain.len() - 1] == b'.';
+
+        match self.kind {
+            RuleKind::Exact => name == self.domain,
+            RuleKind::Subdomains => is_subdomain,
+            RuleKind::Suffix => name == self.domain || is_subdomain,
+        }
+    }
+}
+
+impl fmt::Display for DomainRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            RuleKind::Exact => write!(f, "{}", self.domain),
+            RuleKind::Subdomains => write!(f, "*.{}", self.domain),
+            RuleKind::Suffix => write!(f, "{}/**", self.domain),
+        }
+    }
+}
@@ -0,0 +1,45 @@
+use super::apps::{AppBlocker, SystemAppBlocker};
+use super::website::{blocker_for, load_backend, migrate, save_backend, BlockerBackend, BlockerError, WebsiteBlocker};
+use std::sync::{Arc, RwLock};
+
+/// Blocking backends used by the commands, registered with `app.manage`.
+pub struct BlockerState {
+    website: RwLock<Arc<dyn WebsiteBlocker>>,
+    apps: Arc<dyn AppBlocker>,
+}
+
+impl BlockerState {
+    pub fn new(website: Arc<dyn WebsiteBlocker>, apps: Arc<dyn AppBlocker>) -> Self {
+        BlockerState {
+            website: RwLock::new(website),
+            apps,
+        }
+    }
+
+    /// The configured website backend and the real process blocker.
+    pub fn system() -> Self {
+        Self::new(blocker_for(load_backend()), Arc::new(SystemAppBlocker))
+    }
+
+    pub fn website(&self) -> Arc<dyn WebsiteBlocker> {
+        self.website.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    pub fn apps(&self) -> Arc<dyn AppBlocker> {
+        self.apps.clone()
+    }
+
+    /// Switches the website backend, moving the current block list over to it.
+    pub fn switch_website_backend(&self, backend: BlockerBackend) -> Result<(), BlockerError> {
+        let mut website = self.website.write().unwrap_or_else(|e| e.into_inner());
+        if website.backend() == backend {
+            return Ok(());
+        }
+
+        let new = blocker_for(backend);
+        migrate(website.as_ref(), new.as_ref())?;
+        save_backend(backend)?;
+        *website = new;
+        Ok(())
+    }
+}
@@ -0,0 +1,477 @@
+use super::catalog::{expand_app_ids, get_catalog};
+use super::process::{
+    poll_process_changes, terminate_each_tree, CompiledMatcher, KillReport, MatchSet, ProcessError,
+    ProcessInfo, ProcessMatcher, SkippedProcess, TerminateOptions, TerminationResult,
+};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+use std::time::Duration;
+
+pub const DEFAULT_WATCH_INTERVAL_MS: u64 = 500;
+
+// 每次启动监视都会递增，旧的监视线程发现代数变化后自行退出
+static WATCH_GENERATION: AtomicU64 = AtomicU64::new(0);
+static WATCH_STATE: Lazy<Mutex<WatchState>> = Lazy::new(|| Mutex::new(WatchState::default()));
+static FOCUS_ACTIVE: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WatchMode {
+    /// Act on processes matching `blocked` / `blocked_apps`.
+    #[default]
+    Denylist,
+    /// Act on every catalog application not covered by `allowed` / `allowed_apps`.
+    Allowlist,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WatchOptions {
+    /// Fallback polling interval. With the Linux proc connector this is only a heartbeat.
+    pub interval_ms: u64,
+    pub blocked: Vec<ProcessMatcher>,
+    /// App catalog ids, expanded to matchers for each of the app's executables.
+    pub blocked_apps: Vec<String>,
+    pub mode: WatchMode,
+    pub allowed: Vec<ProcessMatcher>,
+    pub allowed_apps: Vec<String>,
+    /// Only enforce while a focus session is running.
+    pub only_during_focus: bool,
+    /// Terminate blocked processes as soon as they are seen; otherwise only report them.
+    pub auto_kill: bool,
+    pub terminate: TerminateOptions,
+}
+
+impl Default for WatchOptions {
+    fn default() -> Self {
+        WatchOptions {
+            interval_ms: DEFAULT_WATCH_INTERVAL_MS,
+            blocked: Vec::new(),
+            blocked_apps: Vec::new(),
+            mode: WatchMode::default(),
+            allowed: Vec::new(),
+            allowed_apps: Vec::new(),
+            only_during_focus: false,
+            auto_kill: true,
+            terminate: TerminateOptions::default(),
+        }
+    }
+}
+
+impl WatchOptions {
+    /// Whether replacing these options with `other` would let through a process they act on.
+    pub fn loosened_by(&self, other: &WatchOptions) -> bool {
+        let removes_blocked = self.blocked.iter().any(|m| !other.blocked.contains(m))
+            || self.blocked_apps.iter().any(|id| !other.blocked_apps.contains(id));
+        let adds_allowed = other.allowed.iter().any(|m| !self.allowed.contains(m))
+            || other.allowed_apps.iter().any(|id| !self.allowed_apps.contains(id));
+        // 切换模式时两边作用的进程集合不可比较，一律视为放宽
+        removes_blocked
+            || adds_allowed
+            || self.mode != other.mode
+            || (self.auto_kill && !other.auto_kill)
+            || (!self.only_during_focus && other.only_during_focus)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ProcessEvent {
+    Started {
+        process: ProcessInfo,
+    },
+    Exited {
+        process: ProcessInfo,
+    },
+    /// A blocked (or, in allowlist mode, non-allowed) process was seen and
+    /// terminated if `auto_kill` is set.
+    Blocked {
+        mode: WatchMode,
+        matcher: ProcessMatcher,
+        process: ProcessInfo,
+        terminations: Vec<TerminationResult>,
+        skipped: Vec<SkippedProcess>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WatchStatus {
+    pub running: bool,
+    /// "proc_connector" when kernel events drive the scans, otherwise "polling".
+    pub backend: String,
+    pub options: WatchOptions,
+}
+
+#[derive(Default)]
+struct WatchState {
+    running: bool,
+    backend: String,
+    options: WatchOptions,
+    /// Uncompiled form of `matchers.targets`, used to report which rule was hit.
+    targets: Vec<ProcessMatcher>,
+    matchers: Arc<MatchSet>,
+    // 规则或专注状态变化后需要重新检查所有正在运行的进程，而不只是新启动的；
+    // 每个监视线程有自己的标志，旧线程不会抢走新线程的重扫请求
+    rescan: Arc<AtomicBool>,
+}
+
+fn compile_all(matchers: &[ProcessMatcher]) -> Result<Vec<CompiledMatcher>, ProcessError> {
+    matchers.iter().map(|m| m.compile()).collect()
+}
+
+fn lock_state() -> MutexGuard<'static, WatchState> {
+    WATCH_STATE.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+pub fn get_watch_status() -> WatchStatus {
+    let state = lock_state();
+    WatchStatus {
+        running: state.running,
+        backend: state.backend.clone(),
+        options: state.options.clone(),
+    }
+}
+
+/// Replaces the blocked matchers and options of a running (or future) watch.
+pub fn update_watch_options(options: WatchOptions) -> Result<(), ProcessError> {
+    let mut targets = options.blocked.clone();
+    let mut exempt = Vec::new();
+
+    match options.mode {
+        WatchMode::Denylist => {
+            targets.extend(expand_app_ids(&options.blocked_apps)?);
+        }
+        WatchMode::Allowlist => {
+            // 只把目录中的应用视为候选，且只拦截当前用户的图形应用，shell、解释器、后台服务等进程不会被误杀
+            for entry in get_catalog(false) {
+                targets.extend(entry.matchers());
+            }
+            exempt.extend(options.allowed.iter().cloned());
+            exempt.extend(expand_app_ids(&options.allowed_apps)?);
+        }
+    }
+
+    let matchers = MatchSet {
+        targets: compile_all(&targets)?,
+        exempt: compile_all(&exempt)?,
+        gui_apps_only: options.mode == WatchMode::Allowlist,
+    };
+
+    let mut state = lock_state();
+    state.options = options;
+    state.targets = targets;
+    state.matchers = Arc::new(matchers);
+    state.rescan.store(true, Ordering::SeqCst);
+    Ok(())
+}
+
+/// Tells the watch whether a focus session is running, for `only_during_focus`.
+pub fn set_focus_active(active: bool) {
+    let was_active = FOCUS_ACTIVE.swap(active, Ordering::SeqCst);
+    if active && !was_active {
+        lock_state().rescan.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Starts the background watch thread, replacing any previous one. `on_event`
+/// is called from that thread for every start, exit and blocked process.
+pub fn start_watch<F>(options: WatchOptions, on_event: F) -> Result<(), ProcessError>
+where
+    F: Fn(ProcessEvent) + Send + 'static,
+{
+    let rescan = Arc::new(AtomicBool::new(false));
+    let previous = std::mem::replace(&mut lock_state().rescan, rescan.clone());
+    if let Err(e) = update_watch_options(options) {
+        lock_state().rescan = previous;
+        return Err(e);
+    }
+    let generation = WATCH_GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+
+    #[cfg(target_os = "linux")]
+    let connector = match proc_connector::ProcConnector::open() {
+        Ok(connector) => Some(connector),
+        Err(e) => {
+            log::info!("proc connector unavailable, falling back to polling: {}", e);
+            None
+        }
+    };
+    #[cfg(not(target_os = "linux"))]
+    let connector: Option<()> = None;
+
+    let backend = if connector.is_some() { "proc_connector" } else { "polling" };
+
+    thread::Builder::new()
+        .name("process-watch".to_string())
+        .spawn(move || {
+            let mut known = HashMap::new();
+            let mut first_pass = true;
+
+            while WATCH_GENERATION.load(Ordering::SeqCst) == generation {
+                if rescan.swap(false, Ordering::SeqCst) {
+                    known.clear();
+                    first_pass = true;
+                }
+
+                if !first_pass {
+                    let interval = {
+                        let state = lock_state();
+                        Duration::from_millis(state.options.interval_ms.max(50))
+                    };
+                    #[cfg(target_os = "linux")]
+                    match &connector {
+                        // 有内核事件驱动时轮询只作为兜底心跳
+                        Some(connector) => {
+                            if let Err(e) = connector.wait(interval * 10) {
+                                log::warn!("proc connector wait failed: {}", e);
+                                thread::sleep(interval);
+                            }
+                        }
+                        None => thread::sleep(interval),
+                    }
+                    #[cfg(not(target_os = "linux"))]
+                    thread::sleep(interval);
+
+                    if WATCH_GENERATION.load(Ordering::SeqCst) != generation {
+                        break;
+                    }
+                }
+
+                let (matchers, blocked, options) = {
+                    let state = lock_state();
+                    (state.matchers.clone(), state.targets.clone(), state.options.clone())
+                };
+                let enforcing = !options.only_during_focus || FOCUS_ACTIVE.load(Ordering::SeqCst);
+                let diff = match poll_process_changes(&mut known, matchers) {
+                    Ok(diff) => diff,
+                    Err(e) => {
+                        log::warn!("process watch scan failed: {}", e);
+                        if first_pass {
+                            thread::sleep(Duration::from_millis(DEFAULT_WATCH_INTERVAL_MS));
+                        }
+                        continue;
+                    }
+                };
+
+                // 首次扫描只建立基线，不上报启动事件，但已在运行的被屏蔽程序仍会处理
+                if !first_pass {
+                    for process in diff.started {
+                        on_event(ProcessEvent::Started { process });
+                    }
+                    for process in diff.exited {
+                        on_event(ProcessEvent::Exited { process });
+                    }
+                }
+                first_pass = false;
+
+                if !enforcing {
+                    continue;
+                }
+
+                if diff.matched.is_empty() {
+                    continue;
+                }
+                // 同一轮命中的进程一起结束，宽限期只等待一次，不会逐个阻塞监视循环
+                let (indices, processes): (Vec<usize>, Vec<ProcessInfo>) = diff.matched.into_iter().unzip();
+                let mut reports = if options.auto_kill {
+                    match terminate_each_tree(&processes, &options.terminate) {
+                        Ok(reports) => reports,
+                        Err(e) => {
+                            log::warn!("failed to terminate blocked processes: {}", e);
+                            Vec::new()
+                        }
+                    }
+                } else {
+                    Vec::new()
+                };
+                reports.resize_with(processes.len(), KillReport::default);
+
+                for ((index, process), report) in indices.into_iter().zip(processes).zip(reports) {
+                    on_event(ProcessEvent::Blocked {
+                        mode: options.mode,
+                        matcher: blocked[index].clone(),
+                        process,
+                        terminations: report.results,
+                        skipped: report.skipped,
+                    });
+                }
+            }
+        })
+        .map_err(|e| ProcessError::WatchFailed(e.to_string()))?;
+
+    let mut state = lock_state();
+    state.running = true;
+    state.backend = backend.to_string();
+    Ok(())
+}
+
+pub fn stop_watch() {
+    WATCH_GENERATION.fetch_add(1, Ordering::SeqCst);
+    lock_state().running = false;
+}
+
+/// Linux proc connector: the kernel multicasts a message on every exec/exit, so
+/// the watch thread can sleep until something actually happens. Needs
+/// CAP_NET_ADMIN, which Focus Keeper has when running elevated for hosts edits.
+#[cfg(target_os = "linux")]
+mod proc_connector {
+    use std::io;
+    use std::mem;
+    use std::time::{Duration, Instant};
+
+    const CN_IDX_PROC: u32 = 1;
+    const CN_VAL_PROC: u32 = 1;
+    const PROC_CN_MCAST_LISTEN: u32 = 1;
+    const PROC_EVENT_EXEC: u32 = 0x0000_0002;
+    const PROC_EVENT_EXIT: u32 = 0x8000_0000;
+    const NLMSG_HDR_LEN: usize = 16;
+    const CN_MSG_LEN: usize = 20;
+
+    pub struct ProcConnector {
+        fd: libc::c_int,
+    }
+
+    impl ProcConnector {
+        pub fn open() -> io::Result<Self> {
+            let fd = unsafe {
+                libc::socket(
+                    libc::AF_NETLINK,
+                    libc::SOCK_DGRAM | libc::SOCK_CLOEXEC,
+                    libc::NETLINK_CONNECTOR,
+                )
+            };
+            if fd < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let connector = ProcConnector { fd };
+
+            let mut addr: libc::sockaddr_nl = unsafe { mem::zeroed() };
+            addr.nl_family = libc::AF_NETLINK as libc::sa_family_t;
+            addr.nl_groups = CN_IDX_PROC;
+            let bound = unsafe {
+                libc::bind(
+                    fd,
+                    &addr as *const libc::sockaddr_nl as *const libc::sockaddr,
+                    mem::size_of::<libc::sockaddr_nl>() as libc::socklen_t,
+                )
+            };
+            if bound < 0 {
+                return Err(io::Error::last_os_error());
+            }
+
+            let total_len = NLMSG_HDR_LEN + CN_MSG_LEN + 4;
+            let mut msg = Vec::with_capacity(total_len);
+            // nlmsghdr
+            msg.extend_from_slice(&(total_len as u32).to_ne_bytes());
+            msg.extend_from_slice(&(libc::NLMSG_DONE as u16).to_ne_bytes());
+            msg.extend_from_slice(&0u16.to_ne_bytes());
+            msg.extend_from_slice(&0u32.to_ne_bytes());
+            msg.extend_from_slice(&std::process::id().to_ne_bytes());
+            // cn_msg
+            msg.extend_from_slice(&CN_IDX_PROC.to_ne_bytes());
+            msg.extend_from_slice(&CN_VAL_PROC.to_ne_bytes());
+            msg.extend_from_slice(&0u32.to_ne_bytes());
+            msg.extend_from_slice(&0u32.to_ne_bytes());
+            msg.extend_from_slice(&4u16.to_ne_bytes());
+            msg.extend_from_slice(&0u16.to_ne_bytes());
+            // proc_cn_mcast_op
+            msg.extend_from_slice(&PROC_CN_MCAST_LISTEN.to_ne_bytes());
+
+            let sent = unsafe { libc::send(fd, msg.as_ptr() as *const libc::c_void, msg.len(), 0) };
+            if sent < 0 {
+                return Err(io::Error::last_os_error());
+            }
+
+            Ok(connector)
+        }
+
+        /// Blocks until an exec/exit event arrives or `timeout` elapses, draining
+        /// queued messages (fork events etc. are ignored). Returns whether an
+        /// exec/exit was seen.
+        pub fn wait(&self, timeout: Duration) -> io::Result<bool> {
+            let deadline = Instant::now() + timeout;
+            let mut buf = [0u8; 4096];
+
+            loop {
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                if remaining.is_zero() {
+                    return Ok(false);
+                }
+
+                let mut pfd = libc::pollfd {
+                    fd: self.fd,
+                    events: libc::POLLIN,
+                    revents: 0,
+                };
+                let ready = unsafe { libc::poll(&mut pfd, 1, remaining.as_millis() as libc::c_int) };
+                if ready < 0 {
+                    let err = io::Error::last_os_error();
+                    if err.kind() == io::ErrorKind::Interrupted {
+                        continue;
+                    }
+                    return Err(err);
+                }
+                if ready == 0 {
+                    return Ok(false);
+                }
+
+                let mut relevant = false;
+                loop {
+                    let len = unsafe {
+                        libc::recv(
+                            self.fd,
+                            buf.as_mut_ptr() as *mut libc::c_void,
+                            buf.len(),
+                            libc::MSG_DONTWAIT,
+                        )
+                    };
+                    if len <= 0 {
+                        break;
+                    }
+                    relevant |= contains_exec_or_exit(&buf[..len as usize]);
+                }
+                if relevant {
+                    return Ok(true);
+                }
+            }
+        }
+    }
+
+    fn contains_exec_or_exit(mut data: &[u8]) -> bool {
+        while data.len() >= NLMSG_HDR_LEN {
+            let msg_len = u32::from_ne_bytes([data[0], data[1], data[2], data[3]]) as usize;
+            if msg_len < NLMSG_HDR_LEN || msg_len > data.len() {
+                break;
+            }
+            let what_offset = NLMSG_HDR_LEN + CN_MSG_LEN;
+            if msg_len >= what_offset + 4 {
+                let what = u32::from_ne_bytes([
+                    data[what_offset],
+                    data[what_offset + 1],
+                    data[what_offset + 2],
+                    data[what_offset + 3],
+                ]);
+                if what == PROC_EVENT_EXEC || what == PROC_EVENT_EXIT {
+                    return true;
+                }
+            }
+            let aligned = (msg_len + 3) & !3;
+            if aligned >= data.len() {
+                break;
+            }
+            data = &data[aligned..];
+        }
+        false
+    }
+
+    impl Drop for ProcConnector {
+        fn drop(&mut self) {
+            unsafe {
+                libc::close(self.fd);
+            }
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use super::dns::{DnsBlocker, DnsError};
+#[cfg(test)]
+use super::fake::MemoryWebsiteBlocker;
+use super::hosts::{HostsBlocker, HostsError};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum BlockerError {
+    #[error(transparent)]
+    Hosts(#[from] HostsError),
+    #[error(transparent)]
+    Dns(#[from] DnsError),
+    #[error("Invalid domain rule: {0}")]
+    InvalidRule(String),
+    #[error("Unknown category: {0}")]
+    UnknownCategory(String),
+    #[error("Failed to save blocker config: {0}")]
+    Config(std::io::Error),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BlockerBackend {
+    #[default]
+    Hosts,
+    Dns,
+    /// Keeps the list in memory only; test builds only.
+    #[cfg(test)]
+    Memory,
+}
+
+/// A way of keeping the browser away from blocked domains.
+pub trait WebsiteBlocker: Send + Sync {
+    fn backend(&self) -> BlockerBackend;
+    /// The rules as entered by the user.
+    fn list(&self) -> Result<Vec<String>, BlockerError>;
+    /// The concrete names currently enforced; the same as `list` unless rules are expanded.
+    fn entries(&self) -> Result<Vec<String>, BlockerError> {
+        self.list()
+    }
+    fn block(&self, domains: &[String]) -> Result<(), BlockerError>;
+    fn unblock(&self, domain: &str) -> Result<(), BlockerError>;
+    fn clear(&self) -> Result<(), BlockerError>;
+    /// Re-expands rules whose concrete entries can change over time.
+    fn refresh(&self) -> Result<(), BlockerError> {
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct BlockerConfig {
+    backend: BlockerBackend,
+}
+
+fn get_config_path() -> PathBuf {
+    let path = dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("FocusKeeper")
+        .join("blocker-config.json");
+
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    path
+}
+
+fn load_config() -> BlockerConfig {
+    fs::read_to_string(get_config_path())
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+pub fn load_backend() -> BlockerBackend {
+    load_config().backend
+}
+
+pub fn save_backend(backend: BlockerBackend) -> Result<(), BlockerError> {
+    let content = serde_json::to_string_pretty(&BlockerConfig { backend })
+        .map_err(|e| BlockerError::Config(e.into()))?;
+    fs::write(get_config_path(), content).map_err(BlockerError::Config)
+}
+
+pub fn blocker_for(backend: BlockerBackend) -> Arc<dyn WebsiteBlocker> {
+    match backend {
+        BlockerBackend::Hosts => Arc::new(HostsBlocker),
+        BlockerBackend::Dns => Arc::new(DnsBlocker),
+        #[cfg(test)]
+        BlockerBackend::Memory => Arc::new(MemoryWebsiteBlocker::new()),
+    }
+}
+
+/// Moves the block list from `old` to `new`.
+pub fn migrate(old: &dyn WebsiteBlocker, new: &dyn WebsiteBlocker) -> Result<(), BlockerError> {
+    let domains = old.list()?;
+    // 先写入新后端，失败时旧的屏蔽仍然有效
+    new.block(&domains)?;
+    old.clear()
+}
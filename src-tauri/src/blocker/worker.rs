@@ -0,0 +1,125 @@
+use super::process::ProcessError;
+use once_cell::sync::Lazy;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use sysinfo::{System, Users};
+
+/// Upper bound for a single job. A full process refresh normally takes tens of milliseconds.
+pub const WORKER_TIMEOUT: Duration = Duration::from_secs(10);
+
+type Job = Box<dyn FnOnce(&mut ProcessTable) + Send>;
+
+static WORKER: Lazy<Mutex<Option<Sender<Job>>>> = Lazy::new(|| Mutex::new(None));
+
+/// State owned exclusively by the process worker thread.
+pub struct ProcessTable {
+    pub sys: System,
+    pub users: Users,
+    // 当前任务的取消标志，调用方超时后置位
+    cancelled: Arc<AtomicBool>,
+}
+
+impl ProcessTable {
+    fn new() -> Self {
+        let mut sys = System::new();
+        sys.refresh_processes();
+        ProcessTable {
+            sys,
+            users: Users::new_with_refreshed_list(),
+            cancelled: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Whether the caller of the current job has already given up on it.
+    /// Jobs that kill or signal processes check this before each action.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+fn spawn_worker() -> Result<Sender<Job>, ProcessError> {
+    let (sender, receiver) = mpsc::channel::<Job>();
+
+    thread::Builder::new()
+        .name("process-worker".to_string())
+        .spawn(move || {
+            let mut table = ProcessTable::new();
+            while let Ok(job) = receiver.recv() {
+                job(&mut table);
+            }
+        })
+        .map_err(|e| ProcessError::WorkerUnavailable(e.to_string()))?;
+
+    Ok(sender)
+}
+
+fn current_sender() -> Result<Sender<Job>, ProcessError> {
+    let mut worker = WORKER.lock().unwrap_or_else(|e| e.into_inner());
+    match worker.as_ref() {
+        Some(sender) => Ok(sender.clone()),
+        None => {
+            let sender = spawn_worker()?;
+            *worker = Some(sender.clone());
+            Ok(sender)
+        }
+    }
+}
+
+// 丢弃当前 worker，后续请求会启动新线程；卡住的旧线程处理完手头任务后自行退出
+fn reset_worker() {
+    let mut worker = WORKER.lock().unwrap_or_else(|e| e.into_inner());
+    *worker = None;
+}
+
+/// Runs `f` on the process worker thread and waits for its result, giving up
+/// after `timeout`. A timed-out worker is replaced so later calls aren't stuck
+/// behind it.
+pub fn with_table_timeout<T, F>(timeout: Duration, f: F) -> Result<T, ProcessError>
+where
+    T: Send + 'static,
+    F: FnOnce(&mut ProcessTable) -> T + Send + 'static,
+{
+    let (reply_tx, reply_rx) = mpsc::sync_channel(1);
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let job_cancelled = cancelled.clone();
+    let job: Job = Box::new(move |table| {
+        // 调用方已经收到超时错误的任务不再执行，避免在其之后才真正结束进程
+        if job_cancelled.load(Ordering::SeqCst) {
+            return;
+        }
+        table.cancelled = job_cancelled;
+        let _ = reply_tx.send(f(table));
+    });
+
+    if let Err(mpsc::SendError(job)) = current_sender()?.send(job) {
+        reset_worker();
+        current_sender()?
+            .send(job)
+            .map_err(|_| ProcessError::WorkerUnavailable("worker thread exited".to_string()))?;
+    }
+
+    match reply_rx.recv_timeout(timeout) {
+        Ok(value) => Ok(value),
+        Err(RecvTimeoutError::Timeout) => {
+            cancelled.store(true, Ordering::SeqCst);
+            reset_worker();
+            Err(ProcessError::Timeout(timeout.as_millis() as u64))
+        }
+        // 任务没有回复就被丢弃，说明 worker 线程已经退出，下次请求重新启动
+        Err(RecvTimeoutError::Disconnected) => {
+            reset_worker();
+            Err(ProcessError::WorkerPanicked)
+        }
+    }
+}
+
+pub fn with_table<T, F>(f: F) -> Result<T, ProcessError>
+where
+    T: Send + 'static,
+    F: FnOnce(&mut ProcessTable) -> T + Send + 'static,
+{
+    with_table_timeout(WORKER_TIMEOUT, f)
+}
@@ -0,0 +1,155 @@
+use crate::blocker::budget::load_budget_config;
+use crate::blocker::catalog::expand_app_ids;
+use crate::blocker::foreground::{active_window, ActiveWindow, ForegroundError};
+use crate::blocker::process::{CompiledMatcher, ProcessMatcher};
+use crate::blocker::watch::get_watch_status;
+use crate::commands::config::get_blocked_apps;
+use crate::commands::stats::{add_app_usage, AppUsage};
+use chrono::Local;
+use once_cell::sync::Lazy;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tauri::{command, AppHandle, Emitter};
+use tokio::time::{sleep, Duration, Instant};
+
+const SAMPLE_INTERVAL_SECS: u64 = 5;
+// 样本先在内存中累加，定期写入统计文件
+const FLUSH_INTERVAL_SECS: u64 = 60;
+
+static TRACKER_GENERATION: AtomicU64 = AtomicU64::new(0);
+static CURRENT_WINDOW: Lazy<Mutex<Option<ActiveWindow>>> = Lazy::new(|| Mutex::new(None));
+// 编译好的分心应用匹配器；监视、预算或屏蔽应用配置变化时清空，下次采样重新构建
+static DISTRACTING: Lazy<Mutex<Option<Arc<Vec<CompiledMatcher>>>>> = Lazy::new(|| Mutex::new(None));
+
+/// Drops the cached distracting-app matchers. Call after changing the watch,
+/// budget or blocked-app configuration.
+pub fn invalidate_distracting_matchers() {
+    *DISTRACTING.lock().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+fn distracting_matchers() -> Arc<Vec<CompiledMatcher>> {
+    let mut cached = DISTRACTING.lock().unwrap_or_else(|e| e.into_inner());
+    cached.get_or_insert_with(|| Arc::new(build_distracting_matchers())).clone()
+}
+
+// 被屏蔽或设置了预算的应用计为分心时间
+fn build_distracting_matchers() -> Vec<CompiledMatcher> {
+    let watch = get_watch_status().options;
+    let mut matchers: Vec<ProcessMatcher> = get_blocked_apps().into_iter().map(ProcessMatcher::exact).collect();
+    matchers.extend(watch.blocked);
+    matchers.extend(expand_app_ids(&watch.blocked_apps).unwrap_or_default());
+    for budget in load_budget_config().budgets {
+        matchers.extend(budget.matchers().unwrap_or_default());
+    }
+    matchers.iter().filter_map(|m| m.compile().ok()).collect()
+}
+
+fn flush(date: &str, pending: &mut BTreeMap<String, AppUsage>) {
+    if pending.is_empty() {
+        return;
+    }
+    let usage: Vec<AppUsage> = std::mem::take(pending).into_values().collect();
+    if let Err(e) = add_app_usage(date, &usage) {
+        log::warn!("failed to save app usage: {}", e);
+    }
+}
+
+/// Starts sampling the foreground window in the background, replacing any previous tracker.
+pub fn start_activity_tracking(app: AppHandle) {
+    let generation = TRACKER_GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+
+    tauri::async_runtime::spawn(async move {
+        let mut pending: BTreeMap<String, AppUsage> = BTreeMap::new();
+        let mut date = Local::now().format("%Y-%m-%d").to_string();
+        let mut last_sample = Instant::now();
+        let mut last_flush = Instant::now();
+        let mut unsupported_logged = false;
+
+        while TRACKER_GENERATION.load(Ordering::SeqCst) == generation {
+            sleep(Duration::from_secs(SAMPLE_INTERVAL_SECS)).await;
+            // 休眠唤醒后的间隔不计入用量
+            let elapsed = last_sample.elapsed().as_secs().min(SAMPLE_INTERVAL_SECS * 2);
+            last_sample = Instant::now();
+
+            let today = Local::now().format("%Y-%m-%d").to_string();
+            if today != date {
+                flush(&date, &mut pending);
+                date = today;
+            }
+
+            let window = match tauri::async_runtime::spawn_blocking(active_window).await {
+                Ok(Ok(window)) => window,
+                Ok(Err(ForegroundError::Unsupported(reason))) => {
+                    if !unsupported_logged {
+                        log::info!("foreground tracking disabled: {}", reason);
+                        unsupported_logged = true;
+                    }
+                    None
+                }
+                Ok(Err(e)) => {
+                    log::debug!("foreground sample failed: {}", e);
+                    None
+                }
+                Err(e) => {
+                    log::warn!("foreground sample failed: {}", e);
+                    None
+                }
+            };
+
+            let changed = {
+                let mut current = CURRENT_WINDOW.lock().unwrap_or_else(|e| e.into_inner());
+                let changed = match (current.as_ref(), window.as_ref()) {
+                    (Some(a), Some(b)) => a.process.pid != b.process.pid || a.title != b.title,
+                    (None, None) => false,
+                    _ => true,
+                };
+                *current = window.clone();
+                changed
+            };
+            if changed {
+                let _ = app.emit("active-window-changed", window.clone());
+            }
+
+            if let Some(window) = window {
+                let distracting = distracting_matchers().iter().any(|m| m.matches_info(&window.process));
+                let entry = pending
+                    .entry(window.process.name.to_lowercase())
+                    .or_insert_with(|| AppUsage {
+                        name: window.process.name.clone(),
+                        exe: window.process.exe.clone(),
+                        ..Default::default()
+                    });
+                entry.seconds += elapsed;
+                entry.distracting |= distracting;
+            }
+
+            if last_flush.elapsed().as_secs() >= FLUSH_INTERVAL_SECS {
+                flush(&date, &mut pending);
+                last_flush = Instant::now();
+            }
+        }
+        flush(&date, &mut pending);
+    });
+}
+
+/// Stops the tracker after its current sample; buffered usage is still saved.
+pub fn stop_activity_tracking() {
+    TRACKER_GENERATION.fetch_add(1, Ordering::SeqCst);
+}
+
+#[command]
+pub async fn get_active_window() -> Result<Option<ActiveWindow>, String> {
+    Ok(CURRENT_WINDOW.lock().map_err(|e| e.to_string())?.clone())
+}
+
+#[command]
+pub async fn set_activity_tracking(app: AppHandle, enabled: bool) -> Result<bool, String> {
+    if enabled {
+        start_activity_tracking(app);
+    } else {
+        stop_activity_tracking();
+        *CURRENT_WINDOW.lock().map_err(|e| e.to_string())? = None;
+    }
+    Ok(enabled)
+}
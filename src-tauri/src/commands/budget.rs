@@ -0,0 +1,258 @@
+use crate::blocker::budget::{
+    budget_day, load_budget_config, load_budget_state, save_budget_config, save_budget_state,
+    site_budgets_for, Budget, BudgetConfig, BudgetState, BudgetStatus,
+};
+use crate::blocker::process::{sample_running, TerminateOptions, TerminationOutcome};
+use crate::blocker::state::BlockerState;
+use crate::commands::activity::invalidate_distracting_matchers;
+use crate::commands::lock::is_currently_locked;
+use crate::commands::stats::{add_budget_usage, get_budget_usage, record_block, BlockKind};
+use chrono::Local;
+use once_cell::sync::Lazy;
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use tauri::{command, AppHandle, Emitter, Manager};
+use tokio::time::{sleep, Duration, Instant};
+
+// 每次启动跟踪都会递增，旧的跟踪任务发现代数变化后自行退出
+static TRACKER_GENERATION: AtomicU64 = AtomicU64::new(0);
+// 上一次站点用量上报的时间，用来截断重复或过大的上报
+static LAST_SITE_REPORT: Lazy<Mutex<Option<Instant>>> = Lazy::new(|| Mutex::new(None));
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct BudgetResult {
+    pub success: bool,
+    pub message: String,
+    pub config: Option<BudgetConfig>,
+}
+
+fn current_day(config: &BudgetConfig) -> String {
+    budget_day(Local::now(), config.day_start_hour)
+}
+
+fn statuses(config: &BudgetConfig, usage: &BTreeMap<String, u64>) -> Vec<BudgetStatus> {
+    config
+        .budgets
+        .iter()
+        .map(|b| BudgetStatus::new(b, usage.get(&b.id).copied().unwrap_or(0)))
+        .collect()
+}
+
+/// Starts sampling budgeted apps in the background, replacing any previous tracker.
+pub fn start_budget_tracking(app: AppHandle) {
+    let generation = TRACKER_GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+
+    tauri::async_runtime::spawn(async move {
+        let mut last = Instant::now();
+        while TRACKER_GENERATION.load(Ordering::SeqCst) == generation {
+            let interval = load_budget_config().sample_interval_secs;
+            sleep(Duration::from_secs(interval)).await;
+
+            // 休眠唤醒后的间隔不计入用量，最多按两个采样周期计
+            let elapsed = last.elapsed().as_secs_f64().round() as u64;
+            last = Instant::now();
+            if let Err(e) = track_budgets(&app, elapsed.min(interval * 2)).await {
+                log::warn!("budget tracking failed: {}", e);
+            }
+        }
+    });
+}
+
+async fn track_budgets(app: &AppHandle, elapsed: u64) -> Result<(), String> {
+    let config = load_budget_config();
+    let day = current_day(&config);
+    let state = app.state::<BlockerState>();
+    let mut budget_state = load_budget_state();
+
+    if budget_state.day != day {
+        budget_state = BudgetState {
+            day: day.clone(),
+            blocked_sites: budget_state.blocked_sites,
+            ..Default::default()
+        };
+        let _ = app.emit("budget-reset", day.clone());
+    }
+
+    let mut app_budgets: Vec<&Budget> = Vec::new();
+    let mut groups = Vec::new();
+    for budget in config.budgets.iter().filter(|b| b.site_rule().is_none()) {
+        match budget.matchers() {
+            Ok(matchers) => {
+                app_budgets.push(budget);
+                groups.push(matchers);
+            }
+            // 应用被卸载或不在目录中时只跳过这一项，其余预算照常生效
+            Err(e) => log::warn!("skipping budget {}: {}", budget.id, e),
+        }
+    }
+    let running = tauri::async_runtime::spawn_blocking(move || sample_running(&groups))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())?;
+
+    let active: Vec<String> = app_budgets
+        .iter()
+        .zip(&running)
+        .filter(|(_, running)| **running)
+        .map(|(b, _)| b.id.clone())
+        .collect();
+    let usage = if active.is_empty() {
+        get_budget_usage(&day)
+    } else {
+        add_budget_usage(&day, &active, elapsed).map_err(|e| e.to_string())?
+    };
+
+    let mut exhausted_sites = BTreeSet::new();
+    for status in statuses(&config, &usage) {
+        let id = status.budget.id.clone();
+        if let Some(level) = status.warning_level() {
+            if budget_state.warned.get(&id).map(|w| *w < level).unwrap_or(true) {
+                budget_state.warned.insert(id.clone(), level);
+                let _ = app.emit("budget-warning", status.clone());
+            }
+        }
+        if !status.exhausted {
+            continue;
+        }
+        if budget_state.exhausted.insert(id.clone()) {
+            let _ = app.emit("budget-exhausted", status.clone());
+        }
+
+        if let Some(rule) = status.budget.site_rule() {
+            exhausted_sites.insert(rule.to_string());
+        } else if active.contains(&id) {
+            let apps = state.apps();
+            let matchers = match status.budget.matchers() {
+                Ok(matchers) => matchers,
+                Err(e) => {
+                    log::warn!("failed to enforce budget {}: {}", id, e);
+                    continue;
+                }
+            };
+            let report = tauri::async_runtime::spawn_blocking(move || {
+                apps.refresh()?;
+                apps.kill_matching(&matchers, &TerminateOptions::default())
+            })
+            .await
+            .map_err(|e| e.to_string())?;
+            match report {
+                Ok(report) => {
+                    let killed: BTreeSet<String> = report
+                        .results
+                        .iter()
+                        .filter(|r| matches!(r.outcome, TerminationOutcome::Terminated | TerminationOutcome::ForceKilled))
+                        .map(|r| r.name.clone())
+                        .collect();
+                    for name in killed {
+                        if let Err(e) = record_block(BlockKind::App, &name) {
+                            log::warn!("failed to record block of {}: {}", name, e);
+                        }
+                    }
+                    for result in report.results {
+                        let _ = app.emit("process-terminated", result);
+                    }
+                }
+                Err(e) => log::warn!("failed to enforce budget {}: {}", id, e),
+            }
+        }
+    }
+
+    sync_budget_sites(&state, &mut budget_state, &exhausted_sites);
+    save_budget_state(&budget_state).map_err(|e| e.to_string())
+}
+
+// 让预算添加的网站规则等于当前已用尽的网站预算：只增删预算自己加进去的规则
+fn sync_budget_sites(state: &BlockerState, budget_state: &mut BudgetState, exhausted: &BTreeSet<String>) {
+    let website = state.website();
+    let current: BTreeSet<String> = match website.list() {
+        Ok(list) => list.into_iter().collect(),
+        Err(e) => {
+            log::warn!("failed to read block list: {}", e);
+            return;
+        }
+    };
+
+    let to_add: Vec<String> = exhausted.difference(&current).cloned().collect();
+    if !to_add.is_empty() {
+        match website.block(&to_add) {
+            Ok(()) => budget_state.blocked_sites.extend(to_add),
+            Err(e) => log::warn!("failed to block exhausted sites: {}", e),
+        }
+    }
+
+    let to_remove: Vec<String> = budget_state.blocked_sites.difference(exhausted).cloned().collect();
+    for rule in to_remove {
+        match website.unblock(&rule) {
+            Ok(()) => {
+                budget_state.blocked_sites.remove(&rule);
+            }
+            Err(e) => log::warn!("failed to unblock {}: {}", rule, e),
+        }
+    }
+}
+
+#[command]
+pub async fn get_budgets() -> Result<BudgetConfig, String> {
+    Ok(load_budget_config())
+}
+
+#[command]
+pub async fn set_budgets(config: BudgetConfig) -> Result<BudgetResult, String> {
+    if is_currently_locked() {
+        return Ok(BudgetResult {
+            success: false,
+            message: "当前处于锁定模式，无法修改使用预算".to_string(),
+            config: None,
+        });
+    }
+
+    let saved = save_budget_config(config);
+    invalidate_distracting_matchers();
+    match saved {
+        Ok(config) => Ok(BudgetResult {
+            success: true,
+            message: format!("Saved {} budgets", config.budgets.len()),
+            config: Some(config),
+        }),
+        Err(e) => Ok(BudgetResult {
+            success: false,
+            message: e.to_string(),
+            config: None,
+        }),
+    }
+}
+
+#[command]
+pub async fn get_budget_status() -> Result<Vec<BudgetStatus>, String> {
+    let config = load_budget_config();
+    Ok(statuses(&config, &get_budget_usage(&current_day(&config))))
+}
+
+/// Adds foreground time spent on `domain` to the matching site budgets.
+#[command]
+pub async fn report_site_usage(domain: String, seconds: u64) -> Result<Vec<BudgetStatus>, String> {
+    let config = load_budget_config();
+    // 与应用采样一样最多按两个采样周期计，且不超过距上次上报实际经过的时间
+    let interval = config.sample_interval_secs;
+    let seconds = {
+        let mut last = LAST_SITE_REPORT.lock().unwrap_or_else(|e| e.into_inner());
+        let elapsed = last
+            .map(|at| at.elapsed().as_secs_f64().round() as u64)
+            .unwrap_or(interval);
+        *last = Some(Instant::now());
+        seconds.min(elapsed).min(interval * 2)
+    };
+    let day = current_day(&config);
+    let ids = site_budgets_for(&config, &domain);
+    if ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let usage = add_budget_usage(&day, &ids, seconds).map_err(|e| e.to_string())?;
+    Ok(statuses(&config, &usage)
+        .into_iter()
+        .filter(|s| ids.contains(&s.budget.id))
+        .collect())
+}
+
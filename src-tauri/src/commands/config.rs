@@ -0,0 +1,461 @@
+use crate::blocker::categories::{self, enabled_categories, find_category, owned_rules};
+use crate::blocker::dns::{get_resolver_config, is_valid_upstream, set_resolver_config, ResolverConfig};
+use crate::blocker::process::{get_protected_allowlist, set_protected_allowlist};
+use crate::blocker::rules::DomainRule;
+use crate::blocker::state::BlockerState;
+use crate::blocker::watch::{get_watch_status, update_watch_options, WatchOptions};
+use crate::blocker::website::BlockerBackend;
+use crate::commands::activity::invalidate_distracting_matchers;
+use crate::commands::lock::{get_password_hash, is_currently_locked, set_password_hash};
+use chrono::Local;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{command, State};
+
+/// Bump when the bundle layout changes incompatibly.
+pub const CONFIG_VERSION: u32 = 1;
+
+static USER_CONFIG: Lazy<Mutex<UserConfig>> = Lazy::new(|| Mutex::new(load_user_config()));
+
+/// Settings owned by the frontend but stored by the backend so they can be exported.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UserConfig {
+    pub blocked_apps: Vec<String>,
+    pub profiles: Vec<Value>,
+    pub schedules: Vec<Value>,
+    pub settings: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebsiteSection {
+    pub backend: BlockerBackend,
+    pub rules: Vec<String>,
+    #[serde(default)]
+    pub categories: Vec<String>,
+    #[serde(default)]
+    pub dns: ResolverConfig,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppSection {
+    pub blocked: Vec<String>,
+    pub watch: Option<WatchOptions>,
+    pub protected_allowlist: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LockSection {
+    /// Only present when the export explicitly asked for it.
+    pub password_hash: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigBundle {
+    pub version: u32,
+    pub exported_at: String,
+    pub app_version: String,
+    pub websites: WebsiteSection,
+    #[serde(default)]
+    pub apps: AppSection,
+    #[serde(default)]
+    pub profiles: Vec<Value>,
+    #[serde(default)]
+    pub schedules: Vec<Value>,
+    #[serde(default)]
+    pub settings: Map<String, Value>,
+    #[serde(default)]
+    pub lock: LockSection,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfigImportMode {
+    /// Add to the current configuration; imported entries win on conflicts.
+    #[default]
+    Merge,
+    /// Make the current configuration match the bundle.
+    Replace,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConfigResult {
+    pub success: bool,
+    pub message: String,
+    pub errors: Vec<String>,
+    pub bundle: Option<String>,
+}
+
+impl ConfigResult {
+    fn error(message: String, errors: Vec<String>) -> Self {
+        ConfigResult {
+            success: false,
+            message,
+            errors,
+            bundle: None,
+        }
+    }
+}
+
+fn get_user_config_path() -> PathBuf {
+    let path = dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("FocusKeeper")
+        .join("user-config.json");
+
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    path
+}
+
+fn load_user_config() -> UserConfig {
+    fs::read_to_string(get_user_config_path())
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save_user_config(config: &UserConfig) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(config).map_err(|e| e.to_string())?;
+    fs::write(get_user_config_path(), content).map_err(|e| e.to_string())
+}
+
+fn update_user_config<F: FnOnce(&mut UserConfig)>(f: F) -> Result<UserConfig, String> {
+    let mut config = USER_CONFIG.lock().map_err(|e| e.to_string())?;
+    f(&mut config);
+    invalidate_distracting_matchers();
+    save_user_config(&config)?;
+    Ok(config.clone())
+}
+
+pub fn get_blocked_apps() -> Vec<String> {
+    USER_CONFIG
+        .lock()
+        .map(|config| config.blocked_apps.clone())
+        .unwrap_or_default()
+}
+
+#[command]
+pub async fn get_user_config() -> Result<UserConfig, String> {
+    let config = USER_CONFIG.lock().map_err(|e| e.to_string())?;
+    Ok(config.clone())
+}
+
+// 锁定期间不允许从拦截列表里移除应用，与 import_config 一致
+fn check_blocked_apps_kept(apps: &[String]) -> Result<(), String> {
+    if is_currently_locked() && get_blocked_apps().iter().any(|app| !apps.contains(app)) {
+        return Err("当前处于锁定模式，无法移除被拦截的应用".to_string());
+    }
+    Ok(())
+}
+
+#[command]
+pub async fn set_user_config(config: UserConfig) -> Result<UserConfig, String> {
+    check_blocked_apps_kept(&config.blocked_apps)?;
+    update_user_config(|current| *current = config)
+}
+
+#[command]
+pub async fn set_blocked_apps(apps: Vec<String>) -> Result<UserConfig, String> {
+    check_blocked_apps_kept(&apps)?;
+    update_user_config(|config| config.blocked_apps = apps)
+}
+
+#[command]
+pub async fn export_config(
+    state: State<'_, BlockerState>,
+    include_password_hash: Option<bool>,
+) -> Result<ConfigResult, String> {
+    let website = state.website();
+    // 分类自带的规则随 categories 导出，混进 rules 会在导入后变成自定义规则，停用分类也删不掉
+    let owned = owned_rules();
+    let rules: Vec<String> = match website.list() {
+        Ok(rules) => rules.into_iter().filter(|rule| !owned.contains(rule)).collect(),
+        Err(e) => return Ok(ConfigResult::error(e.to_string(), Vec::new())),
+    };
+    let user = USER_CONFIG.lock().map_err(|e| e.to_string())?.clone();
+    let watch = get_watch_status();
+
+    let bundle = ConfigBundle {
+        version: CONFIG_VERSION,
+        exported_at: Local::now().to_rfc3339(),
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        websites: WebsiteSection {
+            backend: website.backend(),
+            rules,
+            categories: enabled_categories(),
+            dns: ResolverConfig {
+                blocked: Vec::new(),
+                ..get_resolver_config()
+            },
+        },
+        apps: AppSection {
+            blocked: user.blocked_apps,
+            watch: Some(watch.options),
+            protected_allowlist: get_protected_allowlist(),
+        },
+        profiles: user.profiles,
+        schedules: user.schedules,
+        settings: user.settings,
+        lock: LockSection {
+            password_hash: if include_password_hash.unwrap_or(false) {
+                get_password_hash()
+            } else {
+                None
+            },
+        },
+    };
+
+    let json = serde_json::to_string_pretty(&bundle).map_err(|e| e.to_string())?;
+    Ok(ConfigResult {
+        success: true,
+        message: format!("Exported {} website rules", bundle.websites.rules.len()),
+        errors: Vec::new(),
+        bundle: Some(json),
+    })
+}
+
+/// Checks a bundle beyond what deserialization already guarantees.
+fn validate_bundle(bundle: &ConfigBundle) -> Vec<String> {
+    let mut errors = Vec::new();
+
+    if bundle.version == 0 || bundle.version > CONFIG_VERSION {
+        errors.push(format!(
+            "unsupported config version {} (expected 1..={})",
+            bundle.version, CONFIG_VERSION
+        ));
+    }
+    for rule in &bundle.websites.rules {
+        if DomainRule::parse(rule).is_none() {
+            errors.push(format!("websites.rules: invalid rule '{}'", rule));
+        }
+    }
+    for id in &bundle.websites.categories {
+        if find_category(id).is_none() {
+            errors.push(format!("websites.categories: unknown category '{}'", id));
+        }
+    }
+    if let Some(upstream) = &bundle.websites.dns.upstream {
+        if !is_valid_upstream(upstream) {
+            errors.push(format!("websites.dns.upstream: invalid server '{}'", upstream));
+        }
+    }
+    if let Some(watch) = &bundle.apps.watch {
+        for matcher in watch.blocked.iter().chain(watch.allowed.iter()) {
+            if let Err(e) = matcher.compile() {
+                errors.push(format!("apps.watch: {}", e));
+            }
+        }
+    }
+    for (field, items) in [("profiles", &bundle.profiles), ("schedules", &bundle.schedules)] {
+        if let Some(index) = items.iter().position(|item| !item.is_object()) {
+            errors.push(format!("{}[{}]: expected an object", field, index));
+        }
+    }
+    errors
+}
+
+// 按 id 合并对象列表：同 id 的条目以导入的为准，其余追加
+fn merge_by_id(current: &mut Vec<Value>, imported: Vec<Value>) {
+    for item in imported {
+        let id = item.get("id").cloned();
+        match id.and_then(|id| current.iter().position(|c| c.get("id") == Some(&id))) {
+            Some(index) => current[index] = item,
+            None if !current.contains(&item) => current.push(item),
+            None => {}
+        }
+    }
+}
+
+fn merge_unique(current: &mut Vec<String>, imported: Vec<String>) {
+    for item in imported {
+        if !current.contains(&item) {
+            current.push(item);
+        }
+    }
+}
+
+/// Everything `apply_bundle` may change, captured so a failed import can be undone.
+struct ConfigSnapshot {
+    backend: BlockerBackend,
+    rules: Vec<String>,
+    categories: categories::CategorySnapshot,
+    dns: ResolverConfig,
+    watch: WatchOptions,
+    protected_allowlist: Vec<String>,
+    user: UserConfig,
+    password_hash: Option<String>,
+}
+
+fn take_snapshot(state: &BlockerState) -> Result<ConfigSnapshot, String> {
+    let website = state.website();
+    Ok(ConfigSnapshot {
+        backend: website.backend(),
+        rules: website.list().map_err(|e| e.to_string())?,
+        categories: categories::snapshot(),
+        dns: get_resolver_config(),
+        watch: get_watch_status().options,
+        protected_allowlist: get_protected_allowlist(),
+        user: USER_CONFIG.lock().map_err(|e| e.to_string())?.clone(),
+        password_hash: get_password_hash(),
+    })
+}
+
+fn restore_snapshot(state: &BlockerState, snapshot: ConfigSnapshot) -> Result<(), String> {
+    state
+        .switch_website_backend(snapshot.backend)
+        .map_err(|e| e.to_string())?;
+    let website = state.website();
+    website.clear().map_err(|e| e.to_string())?;
+    website.block(&snapshot.rules).map_err(|e| e.to_string())?;
+    categories::restore(snapshot.categories).map_err(|e| e.to_string())?;
+    set_resolver_config(snapshot.dns).map_err(|e| e.to_string())?;
+    update_watch_options(snapshot.watch).map_err(|e| e.to_string())?;
+    set_protected_allowlist(&snapshot.protected_allowlist).map_err(|e| e.to_string())?;
+    update_user_config(|user| *user = snapshot.user)?;
+    set_password_hash(snapshot.password_hash)
+}
+
+// 中途失败时恢复导入前的状态，不留下只导入了一半的配置
+fn apply_bundle(state: &BlockerState, bundle: ConfigBundle, mode: ConfigImportMode) -> Result<(), String> {
+    let snapshot = take_snapshot(state)?;
+    let result = match apply_bundle_steps(state, bundle, mode) {
+        Ok(()) => Ok(()),
+        Err(e) => match restore_snapshot(state, snapshot) {
+            Ok(()) => Err(format!("{} (no changes were made)", e)),
+            Err(rollback) => Err(format!("{} (rolling back also failed: {})", e, rollback)),
+        },
+    };
+    // 监视选项和屏蔽应用都可能已变化
+    invalidate_distracting_matchers();
+    result
+}
+
+fn apply_bundle_steps(state: &BlockerState, bundle: ConfigBundle, mode: ConfigImportMode) -> Result<(), String> {
+    let replace = mode == ConfigImportMode::Replace;
+
+    // 网站：替换模式下先切换后端并清空，再写入规则和分类
+    if replace {
+        state
+            .switch_website_backend(bundle.websites.backend)
+            .map_err(|e| e.to_string())?;
+        for id in enabled_categories() {
+            if !bundle.websites.categories.contains(&id) {
+                categories::disable_category(state.website().as_ref(), &id).map_err(|e| e.to_string())?;
+            }
+        }
+        state.website().clear().map_err(|e| e.to_string())?;
+    }
+    let website = state.website();
+    website.block(&bundle.websites.rules).map_err(|e| e.to_string())?;
+    for id in &bundle.websites.categories {
+        categories::enable_category(website.as_ref(), id).map_err(|e| e.to_string())?;
+    }
+
+    // DNS 后端的规则已经通过 block 写入，这里只导入解析器设置
+    let current = get_resolver_config();
+    let imported = bundle.websites.dns;
+    set_resolver_config(ResolverConfig {
+        upstream: if replace { imported.upstream } else { imported.upstream.or(current.upstream) },
+        response: imported.response,
+        blocked: current.blocked,
+    })
+    .map_err(|e| e.to_string())?;
+
+    // 应用屏蔽
+    if let Some(mut watch) = bundle.apps.watch {
+        if !replace {
+            let current = get_watch_status().options;
+            let mut blocked = current.blocked;
+            for matcher in watch.blocked {
+                if !blocked.contains(&matcher) {
+                    blocked.push(matcher);
+                }
+            }
+            watch.blocked = blocked;
+            merge_unique(&mut watch.blocked_apps, current.blocked_apps);
+        }
+        update_watch_options(watch).map_err(|e| e.to_string())?;
+    }
+    let mut allowlist = if replace { Vec::new() } else { get_protected_allowlist() };
+    merge_unique(&mut allowlist, bundle.apps.protected_allowlist);
+    set_protected_allowlist(&allowlist).map_err(|e| e.to_string())?;
+
+    update_user_config(|user| {
+        if replace {
+            user.blocked_apps = bundle.apps.blocked;
+            user.profiles = bundle.profiles;
+            user.schedules = bundle.schedules;
+            user.settings = bundle.settings;
+        } else {
+            merge_unique(&mut user.blocked_apps, bundle.apps.blocked);
+            merge_by_id(&mut user.profiles, bundle.profiles);
+            merge_by_id(&mut user.schedules, bundle.schedules);
+            user.settings.extend(bundle.settings);
+        }
+    })?;
+
+    if let Some(hash) = bundle.lock.password_hash {
+        set_password_hash(Some(hash))?;
+    }
+    Ok(())
+}
+
+#[command]
+pub async fn import_config(
+    state: State<'_, BlockerState>,
+    bundle: String,
+    mode: Option<ConfigImportMode>,
+    dry_run: Option<bool>,
+) -> Result<ConfigResult, String> {
+    if is_currently_locked() {
+        return Ok(ConfigResult::error(
+            "当前处于锁定模式，无法导入配置".to_string(),
+            Vec::new(),
+        ));
+    }
+
+    let bundle: ConfigBundle = match serde_json::from_str(&bundle) {
+        Ok(bundle) => bundle,
+        Err(e) => {
+            return Ok(ConfigResult::error(
+                "Invalid config bundle".to_string(),
+                vec![e.to_string()],
+            ))
+        }
+    };
+    let errors = validate_bundle(&bundle);
+    if !errors.is_empty() {
+        return Ok(ConfigResult::error(
+            format!("Config bundle has {} problems", errors.len()),
+            errors,
+        ));
+    }
+    if dry_run.unwrap_or(false) {
+        return Ok(ConfigResult {
+            success: true,
+            message: "Config bundle is valid".to_string(),
+            errors: Vec::new(),
+            bundle: None,
+        });
+    }
+
+    let mode = mode.unwrap_or_default();
+    let rules = bundle.websites.rules.len();
+    match apply_bundle(&state, bundle, mode) {
+        Ok(()) => Ok(ConfigResult {
+            success: true,
+            message: format!("Imported configuration ({} website rules, {:?})", rules, mode),
+            errors: Vec::new(),
+            bundle: None,
+        }),
+        Err(e) => Ok(ConfigResult::error(e, Vec::new())),
+    }
+}
@@ -1,12 +1,129 @@
-use tauri::{command, AppHandle, Manager, Emitter};
+use tauri::{command, AppHandle, Emitter};
+use std::fs;
+use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{SystemTime, UNIX_EPOCH};
 use tokio::time::{sleep, Duration};
 use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use chrono::Local;
+use crate::blocker::idle::{idle_seconds, IdleError};
+use crate::blocker::reblock::now_secs;
+use crate::blocker::watch::set_focus_active;
+use crate::commands::goals::on_session_finished;
+use crate::commands::stats::{add_focus_seconds, record_focus_session, FocusSession};
+
+const IDLE_CHECK_INTERVAL_SECS: u64 = 5;
+// 计时循环每秒检查一次，墙上时钟间隔远大于此说明系统曾经休眠
+const SUSPEND_GAP_MS: u64 = 15_000;
 
 static FOCUS_RUNNING: Lazy<Arc<AtomicBool>> = Lazy::new(|| Arc::new(AtomicBool::new(false)));
-static FOCUS_ELAPSED: Lazy<Arc<AtomicU64>> = Lazy::new(|| Arc::new(AtomicU64::new(0)));
+static FOCUS_TIMER: Lazy<Mutex<FocusTimer>> = Lazy::new(|| Mutex::new(FocusTimer::default()));
+// 前端在开始和继续时都会调用 run_focus_timer，同一时间只允许一个计时循环
+static TIMER_LOOP_ACTIVE: AtomicBool = AtomicBool::new(false);
 static FOCUS_TARGET: Lazy<Arc<AtomicU64>> = Lazy::new(|| Arc::new(AtomicU64::new(25 * 60)));
+// 因无输入而自动暂停；与用户手动暂停区分，恢复输入后自动继续
+static IDLE_PAUSED: AtomicBool = AtomicBool::new(false);
+// 最后一次输入的时间和自动暂停的时间（Unix 秒）
+static IDLE_SINCE: AtomicU64 = AtomicU64::new(0);
+static IDLE_PAUSED_AT: AtomicU64 = AtomicU64::new(0);
+static SESSION: Lazy<Mutex<Option<FocusSession>>> = Lazy::new(|| Mutex::new(None));
+static IDLE_OPTIONS: Lazy<Mutex<IdleOptions>> = Lazy::new(|| Mutex::new(load_idle_options()));
+static TIMER_OPTIONS: Lazy<Mutex<TimerOptions>> = Lazy::new(|| Mutex::new(load_timer_options()));
+
+/// Session time derived from wall-clock timestamps, so it can't drift when the
+/// runtime is busy and keeps up with `commands::lock`, which also uses wall time.
+#[derive(Debug, Default)]
+struct FocusTimer {
+    /// Unix milliseconds when the current running segment started; `None` while paused.
+    segment_start: Option<u64>,
+    /// Time from finished segments, in milliseconds.
+    accumulated_ms: u64,
+    /// Wall-clock time of the last suspend check.
+    last_check: u64,
+}
+
+impl FocusTimer {
+    fn elapsed_ms(&self, now: u64) -> u64 {
+        self.accumulated_ms + self.segment_start.map(|start| now.saturating_sub(start)).unwrap_or(0)
+    }
+
+    fn start(&mut self, now: u64) {
+        if self.segment_start.is_none() {
+            self.segment_start = Some(now);
+        }
+        self.last_check = now;
+    }
+
+    fn pause(&mut self, now: u64) {
+        if let Some(start) = self.segment_start.take() {
+            self.accumulated_ms += now.saturating_sub(start);
+        }
+    }
+
+    /// Removes `ms` of already counted time, e.g. an idle or suspended period.
+    fn discard(&mut self, ms: u64, now: u64) {
+        self.accumulated_ms = self.elapsed_ms(now).saturating_sub(ms);
+        if self.segment_start.is_some() {
+            self.segment_start = Some(now);
+        }
+    }
+
+    fn credit(&mut self, ms: u64) {
+        self.accumulated_ms += ms;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SuspendPolicy {
+    /// Time the system spent suspended does not count toward the session.
+    #[default]
+    Exclude,
+    Count,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TimerOptions {
+    pub suspend_policy: SuspendPolicy,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct FocusSuspendPayload {
+    pub suspended_seconds: u64,
+    pub counted: bool,
+    pub elapsed: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IdleOptions {
+    pub enabled: bool,
+    /// Minutes without keyboard or mouse input before the session is paused.
+    pub threshold_minutes: u64,
+    /// Leave the idle period out of the focus time; otherwise it is credited on return.
+    pub discard_idle: bool,
+}
+
+impl Default for IdleOptions {
+    fn default() -> Self {
+        IdleOptions {
+            enabled: true,
+            threshold_minutes: 5,
+            discard_idle: true,
+        }
+    }
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct FocusIdlePayload {
+    /// Seconds without input so far, or the whole idle period for `focus-active`.
+    pub idle_seconds: u64,
+    pub elapsed: u64,
+    pub discarded: bool,
+}
 
 #[derive(Clone, serde::Serialize)]
 pub struct FocusState {
@@ -21,36 +138,153 @@ pub struct FocusCompletePayload {
     pub duration: u64,
 }
 
+fn get_idle_options_path() -> PathBuf {
+    let path = dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("FocusKeeper")
+        .join("idle-config.json");
+
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    path
+}
+
+fn get_timer_options_path() -> PathBuf {
+    let path = dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("FocusKeeper")
+        .join("focus-timer.json");
+
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    path
+}
+
+fn load_timer_options() -> TimerOptions {
+    fs::read_to_string(get_timer_options_path())
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn lock_timer() -> MutexGuard<'static, FocusTimer> {
+    FOCUS_TIMER.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn elapsed_seconds() -> u64 {
+    lock_timer().elapsed_ms(now_millis()) / 1000
+}
+
+fn load_idle_options() -> IdleOptions {
+    fs::read_to_string(get_idle_options_path())
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn timer_options() -> TimerOptions {
+    TIMER_OPTIONS.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+fn idle_options() -> IdleOptions {
+    IDLE_OPTIONS.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+fn lock_session() -> MutexGuard<'static, Option<FocusSession>> {
+    SESSION.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Id of the focus session in progress, including one paused for idleness.
+pub fn current_session_id() -> Option<String> {
+    lock_session().as_ref().map(|session| session.id.clone())
+}
+
+// 结束当前的空闲时段并计入会话记录，返回其时长
+fn end_idle_period() -> u64 {
+    if !IDLE_PAUSED.swap(false, Ordering::SeqCst) {
+        return 0;
+    }
+    let idle = now_secs().saturating_sub(IDLE_SINCE.load(Ordering::SeqCst));
+    if let Some(session) = lock_session().as_mut() {
+        session.idle_seconds += idle;
+        session.idle_periods += 1;
+    }
+    idle
+}
+
+fn finish_session(elapsed: u64, completed: bool) -> Option<FocusSession> {
+    let mut session = lock_session().take()?;
+    session.ended_at = Local::now().to_rfc3339();
+    session.focus_seconds = elapsed;
+    session.completed = completed;
+    if let Err(e) = record_focus_session(session.clone()) {
+        log::error!("failed to save focus session: {}", e);
+    }
+    Some(session)
+}
+
+fn begin_session(target_seconds: u64) {
+    let now = Local::now();
+    *lock_session() = Some(FocusSession {
+        id: now.format("%Y%m%d-%H%M%S%.3f").to_string(),
+        started_at: now.to_rfc3339(),
+        target_seconds,
+        ..Default::default()
+    });
+}
+
 #[command]
 pub async fn start_focus(target_minutes: u32, strict_mode: bool) -> Result<FocusState, String> {
     if FOCUS_RUNNING.load(Ordering::SeqCst) {
         return Ok(FocusState {
             is_running: true,
-            elapsed: FOCUS_ELAPSED.load(Ordering::SeqCst),
+            elapsed: elapsed_seconds(),
             target: FOCUS_TARGET.load(Ordering::SeqCst),
             is_strict_mode: strict_mode,
         });
     }
 
-    FOCUS_ELAPSED.store(0, Ordering::SeqCst);
+    // 自动暂停中或手动暂停的旧会话按未完成记录
+    end_idle_period();
+    finish_session(elapsed_seconds(), false);
+    begin_session(target_minutes as u64 * 60);
+
+    {
+        let mut timer = lock_timer();
+        *timer = FocusTimer::default();
+        timer.start(now_millis());
+    }
     FOCUS_TARGET.store(target_minutes as u64 * 60, Ordering::SeqCst);
     FOCUS_RUNNING.store(true, Ordering::SeqCst);
+    set_focus_active(true);
 
     Ok(FocusState {
         is_running: true,
         elapsed: 0,
         target: target_minutes as u64 * 60,
-        is_strict_mode,
+        is_strict_mode: strict_mode,
     })
 }
 
 #[command]
 pub async fn pause_focus() -> Result<FocusState, String> {
     FOCUS_RUNNING.store(false, Ordering::SeqCst);
+    lock_timer().pause(now_millis());
+    end_idle_period();
+    set_focus_active(false);
     
     Ok(FocusState {
         is_running: false,
-        elapsed: FOCUS_ELAPSED.load(Ordering::SeqCst),
+        elapsed: elapsed_seconds(),
         target: FOCUS_TARGET.load(Ordering::SeqCst),
         is_strict_mode: false,
     })
@@ -58,14 +292,32 @@ pub async fn pause_focus() -> Result<FocusState, String> {
 
 #[command]
 pub async fn resume_focus() -> Result<FocusState, String> {
-    if FOCUS_ELAPSED.load(Ordering::SeqCst) >= FOCUS_TARGET.load(Ordering::SeqCst) {
-        FOCUS_ELAPSED.store(0, Ordering::SeqCst);
+    // 空闲暂停的时长计入原来的会话
+    end_idle_period();
+    let now = now_millis();
+    let target = FOCUS_TARGET.load(Ordering::SeqCst);
+    let (restarted, previous_elapsed) = {
+        let mut timer = lock_timer();
+        timer.pause(now);
+        let elapsed = timer.elapsed_ms(now) / 1000;
+        let restarted = elapsed >= target;
+        if restarted {
+            *timer = FocusTimer::default();
+        }
+        timer.start(now);
+        (restarted, elapsed)
+    };
+    // 已完成的计时重新开始，或已没有进行中的会话时，按新会话记录，完成后才会被统计和计入成就
+    if restarted || lock_session().is_none() {
+        finish_session(previous_elapsed.min(target), false);
+        begin_session(target);
     }
     FOCUS_RUNNING.store(true, Ordering::SeqCst);
+    set_focus_active(true);
     
     Ok(FocusState {
         is_running: true,
-        elapsed: FOCUS_ELAPSED.load(Ordering::SeqCst),
+        elapsed: elapsed_seconds(),
         target: FOCUS_TARGET.load(Ordering::SeqCst),
         is_strict_mode: true,
     })
@@ -74,8 +326,15 @@ pub async fn resume_focus() -> Result<FocusState, String> {
 #[command]
 pub async fn stop_focus() -> Result<FocusState, String> {
     FOCUS_RUNNING.store(false, Ordering::SeqCst);
-    let elapsed = FOCUS_ELAPSED.load(Ordering::SeqCst);
-    FOCUS_ELAPSED.store(0, Ordering::SeqCst);
+    set_focus_active(false);
+    end_idle_period();
+    let elapsed = {
+        let mut timer = lock_timer();
+        let elapsed = timer.elapsed_ms(now_millis()) / 1000;
+        *timer = FocusTimer::default();
+        elapsed
+    };
+    finish_session(elapsed, false);
     
     Ok(FocusState {
         is_running: false,
@@ -89,42 +348,94 @@ pub async fn stop_focus() -> Result<FocusState, String> {
 pub async fn get_focus_state() -> Result<FocusState, String> {
     Ok(FocusState {
         is_running: FOCUS_RUNNING.load(Ordering::SeqCst),
-        elapsed: FOCUS_ELAPSED.load(Ordering::SeqCst),
+        elapsed: elapsed_seconds(),
         target: FOCUS_TARGET.load(Ordering::SeqCst),
         is_strict_mode: false,
     })
 }
 
+// 两次检查之间墙上时钟跳过的时间视为系统休眠，按策略决定是否计入
+fn check_suspend(app: &AppHandle) {
+    let now = now_millis();
+    let suspended_ms = {
+        let mut timer = lock_timer();
+        let gap = now.saturating_sub(timer.last_check);
+        timer.last_check = now;
+        if timer.segment_start.is_none() || gap < SUSPEND_GAP_MS {
+            return;
+        }
+        let suspended_ms = gap - 1000;
+        if timer_options().suspend_policy == SuspendPolicy::Exclude {
+            timer.discard(suspended_ms, now);
+        }
+        suspended_ms
+    };
+
+    let counted = timer_options().suspend_policy == SuspendPolicy::Count;
+    if let Some(session) = lock_session().as_mut() {
+        session.suspended_seconds += suspended_ms / 1000;
+    }
+    let _ = app.emit("focus-suspend-detected", FocusSuspendPayload {
+        suspended_seconds: suspended_ms / 1000,
+        counted,
+        elapsed: elapsed_seconds(),
+    });
+}
+
+fn timer_loop_needed() -> bool {
+    FOCUS_RUNNING.load(Ordering::SeqCst) || IDLE_PAUSED.load(Ordering::SeqCst)
+}
+
 #[command]
 pub async fn run_focus_timer(app: AppHandle) -> Result<(), String> {
-    while FOCUS_RUNNING.load(Ordering::SeqCst) {
-        sleep(Duration::from_secs(1)).await;
-        
-        if FOCUS_RUNNING.load(Ordering::SeqCst) {
-            FOCUS_ELAPSED.fetch_add(1, Ordering::SeqCst);
-            
-            let elapsed = FOCUS_ELAPSED.load(Ordering::SeqCst);
-            let target = FOCUS_TARGET.load(Ordering::SeqCst);
-            
-            let _ = app.emit("focus-tick", FocusState {
-                is_running: true,
-                elapsed,
-                target,
-                is_strict_mode: true,
-            });
-
-            if elapsed >= target {
-                FOCUS_RUNNING.store(false, Ordering::SeqCst);
-                
-                let _ = app.emit("focus-complete", FocusCompletePayload {
-                    duration: elapsed,
+    if TIMER_LOOP_ACTIVE.swap(true, Ordering::SeqCst) {
+        return Ok(());
+    }
+
+    loop {
+        // 空闲自动暂停期间保持循环，恢复输入后无需前端重新启动计时
+        while timer_loop_needed() {
+            sleep(Duration::from_secs(1)).await;
+            check_suspend(&app);
+
+            if FOCUS_RUNNING.load(Ordering::SeqCst) {
+                let elapsed = elapsed_seconds();
+                let target = FOCUS_TARGET.load(Ordering::SeqCst);
+
+                let _ = app.emit("focus-tick", FocusState {
+                    is_running: true,
+                    elapsed: elapsed.min(target),
+                    target,
+                    is_strict_mode: true,
                 });
-                
-                break;
+
+                if elapsed >= target {
+                    FOCUS_RUNNING.store(false, Ordering::SeqCst);
+                    lock_timer().pause(now_millis());
+                    set_focus_active(false);
+                    if let Err(e) = add_focus_seconds(target) {
+                        log::error!("failed to record focus time: {}", e);
+                    }
+                    if let Some(session) = finish_session(target, true) {
+                        on_session_finished(&app, &session);
+                    }
+
+                    let _ = app.emit("focus-complete", FocusCompletePayload {
+                        duration: target,
+                    });
+
+                    break;
+                }
             }
         }
+
+        TIMER_LOOP_ACTIVE.store(false, Ordering::SeqCst);
+        // 退出前会话又被继续时，另一次调用可能已因本循环仍在运行而直接返回
+        if !timer_loop_needed() || TIMER_LOOP_ACTIVE.swap(true, Ordering::SeqCst) {
+            break;
+        }
     }
-    
+
     Ok(())
 }
 
@@ -133,9 +444,132 @@ pub fn is_focus_running() -> bool {
 }
 
 pub fn get_elapsed_seconds() -> u64 {
-    FOCUS_ELAPSED.load(Ordering::SeqCst)
+    elapsed_seconds()
 }
 
 pub fn is_strict_mode() -> bool {
     FOCUS_RUNNING.load(Ordering::SeqCst)
 }
+
+pub fn is_idle_paused() -> bool {
+    IDLE_PAUSED.load(Ordering::SeqCst)
+}
+
+fn enter_idle(app: &AppHandle, idle: u64, options: &IdleOptions) {
+    if !FOCUS_RUNNING.swap(false, Ordering::SeqCst) {
+        return;
+    }
+    lock_timer().pause(now_millis());
+    let now = now_secs();
+    IDLE_SINCE.store(now.saturating_sub(idle), Ordering::SeqCst);
+    IDLE_PAUSED_AT.store(now, Ordering::SeqCst);
+    IDLE_PAUSED.store(true, Ordering::SeqCst);
+
+    // 达到阈值前的空闲时间已经被计时器计入，丢弃时要扣回
+    if options.discard_idle {
+        lock_timer().discard(idle * 1000, now_millis());
+    }
+
+    let _ = app.emit("focus-idle", FocusIdlePayload {
+        idle_seconds: idle,
+        elapsed: elapsed_seconds(),
+        discarded: options.discard_idle,
+    });
+}
+
+fn leave_idle(app: &AppHandle, options: &IdleOptions) {
+    let paused_for = now_secs().saturating_sub(IDLE_PAUSED_AT.load(Ordering::SeqCst));
+    let idle = end_idle_period();
+    {
+        let mut timer = lock_timer();
+        if !options.discard_idle {
+            timer.credit(paused_for * 1000);
+        }
+        timer.start(now_millis());
+    }
+    FOCUS_RUNNING.store(true, Ordering::SeqCst);
+
+    let _ = app.emit("focus-active", FocusIdlePayload {
+        idle_seconds: idle,
+        elapsed: elapsed_seconds(),
+        discarded: options.discard_idle,
+    });
+}
+
+/// Watches for missing keyboard/mouse input and pauses the running session
+/// (`focus-idle`), resuming it when input returns (`focus-active`).
+pub fn start_idle_monitor(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut unsupported_logged = false;
+        loop {
+            sleep(Duration::from_secs(IDLE_CHECK_INTERVAL_SECS)).await;
+
+            let options = idle_options();
+            let idle_paused = IDLE_PAUSED.load(Ordering::SeqCst);
+            if !options.enabled {
+                if idle_paused {
+                    leave_idle(&app, &options);
+                }
+                continue;
+            }
+            if !FOCUS_RUNNING.load(Ordering::SeqCst) && !idle_paused {
+                continue;
+            }
+
+            let idle = match tauri::async_runtime::spawn_blocking(idle_seconds).await {
+                Ok(Ok(idle)) => idle,
+                Ok(Err(IdleError::Unsupported(reason))) => {
+                    if !unsupported_logged {
+                        log::info!("idle detection disabled: {}", reason);
+                        unsupported_logged = true;
+                    }
+                    continue;
+                }
+                Ok(Err(e)) => {
+                    log::debug!("idle check failed: {}", e);
+                    continue;
+                }
+                Err(e) => {
+                    log::warn!("idle check failed: {}", e);
+                    continue;
+                }
+            };
+
+            let threshold = options.threshold_minutes.max(1) * 60;
+            if idle_paused && idle < threshold {
+                leave_idle(&app, &options);
+            } else if !idle_paused && idle >= threshold {
+                enter_idle(&app, idle, &options);
+            }
+        }
+    });
+}
+
+#[command]
+pub async fn get_idle_options() -> Result<IdleOptions, String> {
+    Ok(idle_options())
+}
+
+#[command]
+pub async fn set_idle_options(options: IdleOptions) -> Result<IdleOptions, String> {
+    if options.threshold_minutes == 0 {
+        return Err("threshold_minutes must be at least 1".to_string());
+    }
+    let content = serde_json::to_string_pretty(&options).map_err(|e| e.to_string())?;
+    fs::write(get_idle_options_path(), content).map_err(|e| e.to_string())?;
+    *IDLE_OPTIONS.lock().map_err(|e| e.to_string())? = options.clone();
+    Ok(options)
+}
+
+#[command]
+pub async fn get_timer_options() -> Result<TimerOptions, String> {
+    Ok(timer_options())
+}
+
+#[command]
+pub async fn set_timer_options(options: TimerOptions) -> Result<TimerOptions, String> {
+    let content = serde_json::to_string_pretty(&options).map_err(|e| e.to_string())?;
+    fs::write(get_timer_options_path(), content).map_err(|e| e.to_string())?;
+    *TIMER_OPTIONS.lock().map_err(|e| e.to_string())? = options.clone();
+    Ok(options)
+}
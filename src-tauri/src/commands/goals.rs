@@ -0,0 +1,332 @@
+use crate::commands::stats::{load_stats, FocusSession, StatsData};
+use chrono::{Datelike, Duration, Local, NaiveDate};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{command, AppHandle, Emitter};
+
+static GOALS: Lazy<Mutex<Goals>> = Lazy::new(|| Mutex::new(load_goals()));
+// 解锁检查在会话完成时进行，串行化以免同一成就重复解锁
+static ACHIEVEMENTS: Lazy<Mutex<AchievementState>> = Lazy::new(|| Mutex::new(load_achievement_state()));
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Goals {
+    pub daily_minutes: u64,
+    pub weekly_minutes: u64,
+}
+
+impl Default for Goals {
+    fn default() -> Self {
+        Goals {
+            daily_minutes: 60,
+            weekly_minutes: 5 * 60,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GoalProgress {
+    pub goals: Goals,
+    pub today_seconds: u64,
+    pub daily_met: bool,
+    /// Focus time since Monday.
+    pub week_seconds: u64,
+    pub weekly_met: bool,
+    /// Consecutive days meeting the daily goal, ending today (or yesterday while
+    /// today's goal is still open).
+    pub current_streak: u32,
+    pub longest_streak: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Achievement {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    /// RFC 3339 local time, `None` while still locked.
+    pub unlocked_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct AchievementState {
+    unlocked: BTreeMap<String, String>,
+    /// Kept here as well so old focus history can be pruned without losing it.
+    longest_streak: u32,
+}
+
+struct AchievementContext<'a> {
+    stats: &'a StatsData,
+    progress: &'a GoalProgress,
+    session: Option<&'a FocusSession>,
+}
+
+struct AchievementDef {
+    id: &'static str,
+    name: &'static str,
+    description: &'static str,
+    check: fn(&AchievementContext) -> bool,
+}
+
+const ACHIEVEMENT_DEFS: &[AchievementDef] = &[
+    AchievementDef {
+        id: "first_session",
+        name: "初次专注",
+        description: "完成第一次专注",
+        check: |c| c.stats.total_sessions_completed >= 1,
+    },
+    AchievementDef {
+        id: "sessions_10",
+        name: "渐入佳境",
+        description: "累计完成 10 次专注",
+        check: |c| c.stats.total_sessions_completed >= 10,
+    },
+    AchievementDef {
+        id: "sessions_100",
+        name: "百炼成钢",
+        description: "累计完成 100 次专注",
+        check: |c| c.stats.total_sessions_completed >= 100,
+    },
+    AchievementDef {
+        id: "focus_10h",
+        name: "十小时",
+        description: "累计专注 10 小时",
+        check: |c| c.stats.total_focus_seconds >= 10 * 3600,
+    },
+    AchievementDef {
+        id: "focus_100h",
+        name: "百小时",
+        description: "累计专注 100 小时",
+        check: |c| c.stats.total_focus_seconds >= 100 * 3600,
+    },
+    AchievementDef {
+        id: "deep_work",
+        name: "深度工作",
+        description: "完成一次至少 90 分钟的专注",
+        check: |c| c.session.map(|s| s.completed && s.focus_seconds >= 90 * 60).unwrap_or(false),
+    },
+    AchievementDef {
+        id: "undistracted",
+        name: "心无旁骛",
+        description: "完成一次至少 25 分钟且从未离开的专注",
+        check: |c| {
+            c.session
+                .map(|s| s.completed && s.focus_seconds >= 25 * 60 && s.idle_periods == 0)
+                .unwrap_or(false)
+        },
+    },
+    AchievementDef {
+        id: "daily_goal",
+        name: "今日达标",
+        description: "第一次达成每日目标",
+        check: |c| c.progress.daily_met,
+    },
+    AchievementDef {
+        id: "weekly_goal",
+        name: "一周达标",
+        description: "达成每周目标",
+        check: |c| c.progress.weekly_met,
+    },
+    AchievementDef {
+        id: "streak_3",
+        name: "三日连胜",
+        description: "连续 3 天达成每日目标",
+        check: |c| c.progress.current_streak >= 3,
+    },
+    AchievementDef {
+        id: "streak_7",
+        name: "一周连胜",
+        description: "连续 7 天达成每日目标",
+        check: |c| c.progress.current_streak >= 7,
+    },
+    AchievementDef {
+        id: "streak_30",
+        name: "月度坚持",
+        description: "连续 30 天达成每日目标",
+        check: |c| c.progress.current_streak >= 30,
+    },
+];
+
+fn get_data_path(file: &str) -> PathBuf {
+    let path = dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("FocusKeeper")
+        .join(file);
+
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    path
+}
+
+fn load_goals() -> Goals {
+    fs::read_to_string(get_data_path("goals.json"))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn load_achievement_state() -> AchievementState {
+    fs::read_to_string(get_data_path("achievements.json"))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save_achievement_state(state: &AchievementState) -> std::io::Result<()> {
+    let content = serde_json::to_string_pretty(state)?;
+    fs::write(get_data_path("achievements.json"), content)
+}
+
+pub fn goals() -> Goals {
+    GOALS.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+fn met_dates(stats: &StatsData, goal_seconds: u64) -> Vec<NaiveDate> {
+    stats
+        .daily_focus
+        .iter()
+        .filter(|(_, seconds)| **seconds >= goal_seconds)
+        .filter_map(|(date, _)| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok())
+        .collect()
+}
+
+fn longest_run(dates: &[NaiveDate]) -> u32 {
+    let mut longest = 0;
+    let mut run = 0;
+    let mut previous: Option<NaiveDate> = None;
+    for date in dates {
+        run = match previous {
+            Some(p) if *date - p == Duration::days(1) => run + 1,
+            _ => 1,
+        };
+        longest = longest.max(run);
+        previous = Some(*date);
+    }
+    longest
+}
+
+fn compute_progress(stats: &StatsData, goals: &Goals, stored_longest: u32) -> GoalProgress {
+    let today = Local::now().date_naive();
+    let focus_on = |date: NaiveDate| {
+        stats
+            .daily_focus
+            .get(&date.format("%Y-%m-%d").to_string())
+            .copied()
+            .unwrap_or(0)
+    };
+
+    let daily_goal = goals.daily_minutes * 60;
+    let today_seconds = focus_on(today);
+    let monday = today - Duration::days(i64::from(today.weekday().num_days_from_monday()));
+    let week_seconds = monday
+        .iter_days()
+        .take_while(|date| *date <= today)
+        .map(focus_on)
+        .sum();
+
+    // 目标为 0 时不计连胜，避免每天自动达标
+    let dates = if daily_goal == 0 { Vec::new() } else { met_dates(stats, daily_goal) };
+    let mut current_streak = 0;
+    let mut day = if dates.last() == Some(&today) { today } else { today - Duration::days(1) };
+    while dates.binary_search(&day).is_ok() {
+        current_streak += 1;
+        day -= Duration::days(1);
+    }
+
+    GoalProgress {
+        goals: goals.clone(),
+        today_seconds,
+        daily_met: daily_goal > 0 && today_seconds >= daily_goal,
+        week_seconds,
+        weekly_met: goals.weekly_minutes > 0 && week_seconds >= goals.weekly_minutes * 60,
+        current_streak,
+        longest_streak: longest_run(&dates).max(stored_longest).max(current_streak),
+    }
+}
+
+/// Re-evaluates achievements after a focus session finished; returns the newly unlocked ones.
+pub fn evaluate_achievements(session: Option<&FocusSession>) -> Vec<Achievement> {
+    let mut state = ACHIEVEMENTS.lock().unwrap_or_else(|e| e.into_inner());
+    let stats = load_stats();
+    let progress = compute_progress(&stats, &goals(), state.longest_streak);
+    let context = AchievementContext {
+        stats: &stats,
+        progress: &progress,
+        session,
+    };
+
+    let now = Local::now().to_rfc3339();
+    let mut unlocked = Vec::new();
+    for def in ACHIEVEMENT_DEFS {
+        if state.unlocked.contains_key(def.id) || !(def.check)(&context) {
+            continue;
+        }
+        state.unlocked.insert(def.id.to_string(), now.clone());
+        unlocked.push(to_achievement(def, Some(now.clone())));
+    }
+
+    let changed = !unlocked.is_empty() || progress.longest_streak != state.longest_streak;
+    state.longest_streak = progress.longest_streak;
+    if changed {
+        if let Err(e) = save_achievement_state(&state) {
+            log::error!("failed to save achievements: {}", e);
+        }
+    }
+    unlocked
+}
+
+/// Evaluates achievements for a finished session and emits `achievement-unlocked` for each new one.
+pub fn on_session_finished(app: &AppHandle, session: &FocusSession) {
+    for achievement in evaluate_achievements(Some(session)) {
+        let _ = app.emit("achievement-unlocked", achievement);
+    }
+}
+
+fn to_achievement(def: &AchievementDef, unlocked_at: Option<String>) -> Achievement {
+    Achievement {
+        id: def.id.to_string(),
+        name: def.name.to_string(),
+        description: def.description.to_string(),
+        unlocked_at,
+    }
+}
+
+#[command]
+pub async fn get_goals() -> Result<Goals, String> {
+    Ok(goals())
+}
+
+#[command]
+pub async fn set_goals(goals: Goals) -> Result<Goals, String> {
+    if goals.daily_minutes > 24 * 60 || goals.weekly_minutes > 7 * 24 * 60 {
+        return Err("Goal exceeds the length of the period".to_string());
+    }
+    let content = serde_json::to_string_pretty(&goals).map_err(|e| e.to_string())?;
+    fs::write(get_data_path("goals.json"), content).map_err(|e| e.to_string())?;
+    *GOALS.lock().map_err(|e| e.to_string())? = goals.clone();
+    Ok(goals)
+}
+
+pub fn goal_progress(stats: &StatsData) -> GoalProgress {
+    let longest = ACHIEVEMENTS.lock().unwrap_or_else(|e| e.into_inner()).longest_streak;
+    compute_progress(stats, &goals(), longest)
+}
+
+#[command]
+pub async fn get_goal_progress() -> Result<GoalProgress, String> {
+    Ok(goal_progress(&load_stats()))
+}
+
+#[command]
+pub async fn get_achievements() -> Result<Vec<Achievement>, String> {
+    let state = ACHIEVEMENTS.lock().map_err(|e| e.to_string())?;
+    Ok(ACHIEVEMENT_DEFS
+        .iter()
+        .map(|def| to_achievement(def, state.unlocked.get(def.id).cloned()))
+        .collect())
+}
@@ -1,9 +1,41 @@
-use crate::blocker::hosts::{
-    add_blocked_website, add_blocked_websites, clear_all_blocks, 
-    read_blocked_websites, remove_blocked_website
+use crate::blocker::categories::{self, get_categories, CategoryInfo};
+use crate::blocker::dns::{get_resolver_config, set_resolver_config, BlockResponse, ResolverConfig};
+use crate::blocker::import::{preview_import, ImportFormat, ImportPreview};
+use crate::blocker::hosts::{allow_only_status, disable_allow_only, enable_allow_only};
+use crate::blocker::reblock::{
+    complete_reblock, find_pending, get_pending_reblocks, now_secs, schedule_reblock, PendingReblock,
+    MAX_TEMPORARY_UNBLOCK_MINUTES
 };
-use crate::commands::lock::is_currently_locked;
-use tauri::command;
+use crate::blocker::rules::DomainRule;
+use crate::blocker::state::BlockerState;
+use crate::blocker::website::{BlockerBackend, BlockerError};
+use crate::commands::lock::{check_password, is_currently_locked};
+use crate::commands::stats::{record_block, record_temporary_unblock, BlockKind};
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::sync::mpsc::{channel, Sender};
+use std::sync::Mutex;
+use tauri::{command, AppHandle, Emitter, Manager, State};
+use tokio::time::{sleep, Duration};
+
+const REBLOCK_CHECK_INTERVAL_SECS: u64 = 30;
+const REBLOCK_RETRY_SECS: u64 = 60;
+// 浏览器会对同一域名反复查询，同一域名在此时间内只记一次拦截
+const DNS_BLOCK_COOLDOWN_SECS: u64 = 60;
+
+static DNS_BLOCKS_SEEN: Lazy<Mutex<HashMap<String, u64>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+// 所有被拦截的查询由同一个线程依次写入统计
+static DNS_BLOCK_WRITER: Lazy<Mutex<Sender<String>>> = Lazy::new(|| {
+    let (sender, receiver) = channel::<String>();
+    std::thread::spawn(move || {
+        for domain in receiver {
+            if let Err(e) = record_block(BlockKind::Website, &domain) {
+                log::warn!("failed to record block of {}: {}", domain, e);
+            }
+        }
+    });
+    Mutex::new(sender)
+});
 
 #[derive(serde::Serialize, serde::Deserialize, Debug)]
 pub struct HostsResult {
@@ -13,8 +45,8 @@ pub struct HostsResult {
 }
 
 #[command]
-pub async fn get_blocked_websites() -> Result<HostsResult, String> {
-    match read_blocked_websites() {
+pub async fn get_blocked_websites(state: State<'_, BlockerState>) -> Result<HostsResult, String> {
+    match state.website().list() {
         Ok(websites) => Ok(HostsResult {
             success: true,
             message: "Successfully retrieved blocked websites".to_string(),
@@ -29,7 +61,59 @@ pub async fn get_blocked_websites() -> Result<HostsResult, String> {
 }
 
 #[command]
-pub async fn add_website(domain: String) -> Result<HostsResult, String> {
+pub async fn get_blocked_entries(state: State<'_, BlockerState>) -> Result<HostsResult, String> {
+    match state.website().entries() {
+        Ok(entries) => Ok(HostsResult {
+            success: true,
+            message: format!("{} entries are currently blocked", entries.len()),
+            data: Some(entries),
+        }),
+        Err(e) => Ok(HostsResult {
+            success: false,
+            message: e.to_string(),
+            data: None,
+        }),
+    }
+}
+
+#[command]
+pub async fn refresh_website_rules(state: State<'_, BlockerState>) -> Result<HostsResult, String> {
+    let website = state.website();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        website.refresh()?;
+        website.entries()
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+    match result {
+        Ok(entries) => Ok(HostsResult {
+            success: true,
+            message: format!("Rules expanded to {} entries", entries.len()),
+            data: Some(entries),
+        }),
+        Err(e) => Ok(HostsResult {
+            success: false,
+            message: e.to_string(),
+            data: None,
+        }),
+    }
+}
+
+// hosts 后端看不到实际被拦下的访问，沿用原来的口径在加入规则时各记一次；DNS 后端由解析线程按查询记录
+fn record_added_websites(backend: BlockerBackend, domains: &[String]) {
+    if backend == BlockerBackend::Dns {
+        return;
+    }
+    for domain in domains {
+        let target = DomainRule::parse(domain).map(|rule| rule.to_string()).unwrap_or_else(|| domain.clone());
+        if let Err(e) = record_block(BlockKind::Website, &target) {
+            log::warn!("failed to record block of {}: {}", target, e);
+        }
+    }
+}
+
+#[command]
+pub async fn add_website(state: State<'_, BlockerState>, domain: String) -> Result<HostsResult, String> {
     if is_currently_locked() {
         return Ok(HostsResult {
             success: false,
@@ -37,10 +121,45 @@ pub async fn add_website(domain: String) -> Result<HostsResult, String> {
             data: None,
         });
     }
-    match add_blocked_website(&domain) {
+    let website = state.website();
+    let domains = vec![domain.clone()];
+    // 通配规则展开需要读取 DNS 缓存并重写 hosts 文件，放到阻塞线程执行
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        website.block(&domains)?;
+        Ok::<_, BlockerError>((website.backend(), domains))
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+    match result {
+        Ok((backend, domains)) => {
+            record_added_websites(backend, &domains);
+            Ok(HostsResult {
+                success: true,
+                message: format!("Successfully blocked {}", domain),
+                data: None,
+            })
+        }
+        Err(e) => Ok(HostsResult {
+            success: false,
+            message: e.to_string(),
+            data: None,
+        }),
+    }
+}
+
+#[command]
+pub async fn remove_website(state: State<'_, BlockerState>, domain: String) -> Result<HostsResult, String> {
+    if is_currently_locked() {
+        return Ok(HostsResult {
+            success: false,
+            message: "当前处于锁定模式，无法修改屏蔽列表".to_string(),
+            data: None,
+        });
+    }
+    match state.website().unblock(&domain) {
         Ok(()) => Ok(HostsResult {
             success: true,
-            message: format!("Successfully blocked {}", domain),
+            message: format!("Successfully unblocked {}", domain),
             data: None,
         }),
         Err(e) => Ok(HostsResult {
@@ -52,7 +171,33 @@ pub async fn add_website(domain: String) -> Result<HostsResult, String> {
 }
 
 #[command]
-pub async fn remove_website(domain: String) -> Result<HostsResult, String> {
+pub async fn block_websites(state: State<'_, BlockerState>, domains: Vec<String>) -> Result<HostsResult, String> {
+    let website = state.website();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        website.block(&domains)?;
+        Ok::<_, BlockerError>((website.backend(), domains))
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+    match result {
+        Ok((backend, domains)) => {
+            record_added_websites(backend, &domains);
+            Ok(HostsResult {
+                success: true,
+                message: format!("Successfully blocked {} websites", domains.len()),
+                data: None,
+            })
+        }
+        Err(e) => Ok(HostsResult {
+            success: false,
+            message: e.to_string(),
+            data: None,
+        }),
+    }
+}
+
+#[command]
+pub async fn unblock_all(state: State<'_, BlockerState>) -> Result<HostsResult, String> {
     if is_currently_locked() {
         return Ok(HostsResult {
             success: false,
@@ -60,10 +205,10 @@ pub async fn remove_website(domain: String) -> Result<HostsResult, String> {
             data: None,
         });
     }
-    match remove_blocked_website(&domain) {
+    match state.website().clear() {
         Ok(()) => Ok(HostsResult {
             success: true,
-            message: format!("Successfully unblocked {}", domain),
+            message: "Successfully unblocked all websites".to_string(),
             data: None,
         }),
         Err(e) => Ok(HostsResult {
@@ -75,11 +220,41 @@ pub async fn remove_website(domain: String) -> Result<HostsResult, String> {
 }
 
 #[command]
-pub async fn block_websites(domains: Vec<String>) -> Result<HostsResult, String> {
-    match add_blocked_websites(&domains) {
+pub async fn enable_allow_only_mode(domains: Vec<String>) -> Result<HostsResult, String> {
+    let count = domains.len();
+    let result = tauri::async_runtime::spawn_blocking(move || enable_allow_only(&domains))
+        .await
+        .map_err(|e| e.to_string())?;
+    match result {
         Ok(()) => Ok(HostsResult {
             success: true,
-            message: format!("Successfully blocked {} websites", domains.len()),
+            message: format!("Allow-only mode enabled for {} domains", count),
+            data: Some(allow_only_status().allowed),
+        }),
+        Err(e) => Ok(HostsResult {
+            success: false,
+            message: e.to_string(),
+            data: None,
+        }),
+    }
+}
+
+#[command]
+pub async fn disable_allow_only_mode() -> Result<HostsResult, String> {
+    if is_currently_locked() {
+        return Ok(HostsResult {
+            success: false,
+            message: "当前处于锁定模式，无法修改屏蔽列表".to_string(),
+            data: None,
+        });
+    }
+    let result = tauri::async_runtime::spawn_blocking(disable_allow_only)
+        .await
+        .map_err(|e| e.to_string())?;
+    match result {
+        Ok(()) => Ok(HostsResult {
+            success: true,
+            message: "Allow-only mode disabled".to_string(),
             data: None,
         }),
         Err(e) => Ok(HostsResult {
@@ -91,7 +266,26 @@ pub async fn block_websites(domains: Vec<String>) -> Result<HostsResult, String>
 }
 
 #[command]
-pub async fn unblock_all() -> Result<HostsResult, String> {
+pub async fn get_allow_only_state() -> Result<HostsResult, String> {
+    let status = allow_only_status();
+    Ok(HostsResult {
+        success: true,
+        message: if status.running {
+            format!("Allow-only mode is active, forwarding to {}", status.upstream.unwrap_or_default())
+        } else {
+            "Allow-only mode is inactive".to_string()
+        },
+        data: Some(status.allowed),
+    })
+}
+
+#[command]
+pub async fn get_blocking_backend(state: State<'_, BlockerState>) -> Result<BlockerBackend, String> {
+    Ok(state.website().backend())
+}
+
+#[command]
+pub async fn set_blocking_backend(state: State<'_, BlockerState>, backend: BlockerBackend) -> Result<HostsResult, String> {
     if is_currently_locked() {
         return Ok(HostsResult {
             success: false,
@@ -99,12 +293,62 @@ pub async fn unblock_all() -> Result<HostsResult, String> {
             data: None,
         });
     }
-    match clear_all_blocks() {
+    match state.switch_website_backend(backend) {
         Ok(()) => Ok(HostsResult {
             success: true,
-            message: "Successfully unblocked all websites".to_string(),
+            message: format!("Switched website blocking to {:?} backend", backend),
+            data: state.website().list().ok(),
+        }),
+        Err(e) => Ok(HostsResult {
+            success: false,
+            message: e.to_string(),
+            data: None,
+        }),
+    }
+}
+
+#[command]
+pub async fn get_dns_config() -> Result<ResolverConfig, String> {
+    Ok(get_resolver_config())
+}
+
+#[command]
+pub async fn set_dns_config(upstream: Option<String>, response: Option<BlockResponse>) -> Result<HostsResult, String> {
+    let mut config = get_resolver_config();
+    config.upstream = upstream.filter(|u| !u.trim().is_empty());
+    if let Some(response) = response {
+        config.response = response;
+    }
+    let result = tauri::async_runtime::spawn_blocking(move || set_resolver_config(config))
+        .await
+        .map_err(|e| e.to_string())?;
+    match result {
+        Ok(()) => Ok(HostsResult {
+            success: true,
+            message: "DNS settings updated".to_string(),
+            data: None,
+        }),
+        Err(e) => Ok(HostsResult {
+            success: false,
+            message: e.to_string(),
             data: None,
         }),
+    }
+}
+
+#[command]
+pub async fn get_block_categories() -> Result<Vec<CategoryInfo>, String> {
+    Ok(get_categories())
+}
+
+#[command]
+pub async fn enable_category(state: State<'_, BlockerState>, id: String) -> Result<HostsResult, String> {
+    match categories::enable_category(state.website().as_ref(), &id) {
+        Ok(()) => Ok(HostsResult {
+            success: true,
+            message: format!("Enabled category {}", id),
+            data: state.website().list().ok(),
+        }),
         Err(e) => Ok(HostsResult {
             success: false,
             message: e.to_string(),
@@ -112,3 +356,218 @@ pub async fn unblock_all() -> Result<HostsResult, String> {
         }),
     }
 }
+
+#[command]
+pub async fn disable_category(state: State<'_, BlockerState>, id: String) -> Result<HostsResult, String> {
+    if is_currently_locked() {
+        return Ok(HostsResult {
+            success: false,
+            message: "当前处于锁定模式，无法修改屏蔽列表".to_string(),
+            data: None,
+        });
+    }
+    match categories::disable_category(state.website().as_ref(), &id) {
+        Ok(()) => Ok(HostsResult {
+            success: true,
+            message: format!("Disabled category {}", id),
+            data: state.website().list().ok(),
+        }),
+        Err(e) => Ok(HostsResult {
+            success: false,
+            message: e.to_string(),
+            data: None,
+        }),
+    }
+}
+
+#[command]
+pub async fn preview_blocklist_import(
+    state: State<'_, BlockerState>,
+    content: String,
+    format: Option<ImportFormat>,
+) -> Result<ImportPreview, String> {
+    let existing = state.website().list().map_err(|e| e.to_string())?;
+    Ok(preview_import(&content, format.unwrap_or_default(), &existing))
+}
+
+#[command]
+pub async fn import_blocklist(
+    state: State<'_, BlockerState>,
+    content: String,
+    format: Option<ImportFormat>,
+) -> Result<HostsResult, String> {
+    let existing = state.website().list().map_err(|e| e.to_string())?;
+    let preview = preview_import(&content, format.unwrap_or_default(), &existing);
+    let rules = preview.rules();
+
+    match state.website().block(&rules) {
+        Ok(()) => Ok(HostsResult {
+            success: true,
+            message: format!(
+                "Imported {} rules ({} duplicates, {} rejected)",
+                rules.len(),
+                preview.duplicates.len(),
+                preview.rejected.len()
+            ),
+            data: Some(rules),
+        }),
+        Err(e) => Ok(HostsResult {
+            success: false,
+            message: e.to_string(),
+            data: None,
+        }),
+    }
+}
+
+// 按墙上时钟分段等待，系统休眠唤醒后也能按时重新屏蔽；失败时定期重试直到成功
+fn spawn_reblock(app: AppHandle, entry: PendingReblock) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let now = now_secs();
+            if now < entry.reblock_at {
+                let wait = (entry.reblock_at - now).min(REBLOCK_CHECK_INTERVAL_SECS);
+                sleep(Duration::from_secs(wait)).await;
+                continue;
+            }
+            // 期间被延长或取消时由新的任务负责
+            if find_pending(&entry.rule).as_ref() != Some(&entry) {
+                return;
+            }
+
+            let state = app.state::<BlockerState>();
+            match state.website().block(std::slice::from_ref(&entry.rule)) {
+                Ok(()) => {
+                    if let Err(e) = complete_reblock(&entry) {
+                        log::error!("failed to save pending re-blocks: {}", e);
+                    }
+                    let _ = app.emit("website-reblocked", entry.clone());
+                    return;
+                }
+                Err(e) => {
+                    log::error!("failed to re-block {}: {}", entry.rule, e);
+                    sleep(Duration::from_secs(REBLOCK_RETRY_SECS)).await;
+                }
+            }
+        }
+    });
+}
+
+/// Schedules the re-blocks persisted by a previous run; overdue ones happen right away.
+pub fn resume_pending_reblocks(app: &AppHandle) {
+    for entry in get_pending_reblocks() {
+        spawn_reblock(app.clone(), entry);
+    }
+}
+
+/// Records queries blocked by the local resolver as website blocks, once per domain per cooldown.
+pub fn record_dns_block(name: &str) {
+    let domain = name.trim_end_matches('.').to_lowercase();
+    let now = now_secs();
+    {
+        let mut seen = DNS_BLOCKS_SEEN.lock().unwrap_or_else(|e| e.into_inner());
+        if seen.get(&domain).is_some_and(|last| now.saturating_sub(*last) < DNS_BLOCK_COOLDOWN_SECS) {
+            return;
+        }
+        seen.retain(|_, last| now.saturating_sub(*last) < DNS_BLOCK_COOLDOWN_SECS);
+        seen.insert(domain.clone(), now);
+    }
+    // 写统计文件较慢，交给写入线程，不占用解析线程
+    let _ = DNS_BLOCK_WRITER.lock().unwrap_or_else(|e| e.into_inner()).send(domain);
+}
+
+#[command]
+pub async fn temporarily_unblock(
+    app: AppHandle,
+    state: State<'_, BlockerState>,
+    domain: String,
+    minutes: u64,
+    password: Option<String>,
+) -> Result<HostsResult, String> {
+    if minutes == 0 || minutes > MAX_TEMPORARY_UNBLOCK_MINUTES {
+        return Ok(HostsResult {
+            success: false,
+            message: format!("临时解除时长必须在 1 到 {} 分钟之间", MAX_TEMPORARY_UNBLOCK_MINUTES),
+            data: None,
+        });
+    }
+    if is_currently_locked() && !password.as_deref().map(check_password).unwrap_or(false) {
+        return Ok(HostsResult {
+            success: false,
+            message: "当前处于锁定模式，需要密码才能临时解除屏蔽".to_string(),
+            data: None,
+        });
+    }
+
+    let rule = match DomainRule::parse(&domain) {
+        Some(rule) => rule.to_string(),
+        None => {
+            return Ok(HostsResult {
+                success: false,
+                message: format!("Invalid domain: {}", domain),
+                data: None,
+            })
+        }
+    };
+    let website = state.website();
+    if !website.list().map_err(|e| e.to_string())?.contains(&rule) {
+        return Ok(HostsResult {
+            success: false,
+            message: format!("{} is not blocked", rule),
+            data: None,
+        });
+    }
+
+    // 先持久化重新屏蔽计划再删除条目，中途崩溃也不会永久解除
+    let entry = schedule_reblock(&rule, minutes).map_err(|e| e.to_string())?;
+    if let Err(e) = website.unblock(&rule) {
+        let _ = complete_reblock(&entry);
+        return Ok(HostsResult {
+            success: false,
+            message: e.to_string(),
+            data: None,
+        });
+    }
+    if let Err(e) = record_temporary_unblock() {
+        log::error!("failed to record temporary unblock: {}", e);
+    }
+    spawn_reblock(app, entry);
+
+    Ok(HostsResult {
+        success: true,
+        message: format!("Unblocked {} for {} minutes", rule, minutes),
+        data: Some(vec![rule]),
+    })
+}
+
+#[command]
+pub async fn cancel_temporary_unblock(state: State<'_, BlockerState>, domain: String) -> Result<HostsResult, String> {
+    let entry = DomainRule::parse(&domain).and_then(|rule| find_pending(&rule.to_string()));
+    let Some(entry) = entry else {
+        return Ok(HostsResult {
+            success: false,
+            message: format!("{} is not temporarily unblocked", domain),
+            data: None,
+        });
+    };
+
+    match state.website().block(std::slice::from_ref(&entry.rule)) {
+        Ok(()) => {
+            complete_reblock(&entry).map_err(|e| e.to_string())?;
+            Ok(HostsResult {
+                success: true,
+                message: format!("Blocked {} again", entry.rule),
+                data: Some(vec![entry.rule]),
+            })
+        }
+        Err(e) => Ok(HostsResult {
+            success: false,
+            message: e.to_string(),
+            data: None,
+        }),
+    }
+}
+
+#[command]
+pub async fn get_temporary_unblocks() -> Result<Vec<PendingReblock>, String> {
+    Ok(get_pending_reblocks())
+}
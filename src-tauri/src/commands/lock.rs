@@ -236,3 +236,25 @@ pub fn is_currently_locked() -> bool {
     
     false
 }
+
+/// The stored password hash, for configuration export.
+pub fn get_password_hash() -> Option<String> {
+    LOCK_STATE.lock().ok().and_then(|state| state.password_hash.clone())
+}
+
+/// Replaces the stored password hash, for configuration import.
+pub fn set_password_hash(hash: Option<String>) -> Result<(), String> {
+    let mut state = LOCK_STATE.lock().map_err(|e| e.to_string())?;
+    state.password_hash = hash;
+    state.save()
+}
+
+/// Checks `password` against the lock password without changing lock state.
+pub fn check_password(password: &str) -> bool {
+    LOCK_STATE
+        .lock()
+        .ok()
+        .and_then(|state| state.password_hash.clone())
+        .map(|hash| hash_password(password) == hash)
+        .unwrap_or(false)
+}
@@ -3,3 +3,12 @@ pub mod process;
 pub mod stats;
 pub mod focus;
 pub mod lock;
+pub mod config;
+pub mod budget;
+pub mod activity;
+pub mod goals;
+pub mod report;
+pub mod stats_store;
+
+#[cfg(test)]
+mod tests;
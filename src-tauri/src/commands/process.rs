@@ -1,9 +1,18 @@
+use crate::blocker::catalog::{expand_app_ids, get_catalog, AppEntry};
 use crate::blocker::process::{
-    find_processes_by_name, get_running_apps, is_app_running, 
-    kill_process, kill_processes_by_name, refresh_system
+    get_protected_allowlist, loosens_protected_allowlist, set_protected_allowlist, MatchMode, ProcessError,
+    ProcessMatcher,
 };
-use crate::blocker::process::ProcessInfo;
-use tauri::command;
+use crate::blocker::process::{ProcessInfo, SkippedProcess, TerminateOptions, TerminationResult};
+use crate::blocker::state::BlockerState;
+use crate::blocker::watch::{
+    get_watch_status, start_watch, stop_watch, update_watch_options, ProcessEvent, WatchOptions,
+    WatchStatus,
+};
+use crate::commands::activity::invalidate_distracting_matchers;
+use crate::commands::lock::is_currently_locked;
+use crate::commands::stats::{record_block, BlockKind};
+use tauri::{command, AppHandle, Emitter, Runtime, State};
 
 #[derive(serde::Serialize, serde::Deserialize, Debug)]
 pub struct ProcessResult {
@@ -11,53 +20,194 @@ pub struct ProcessResult {
     pub message: String,
     pub processes: Option<Vec<ProcessInfo>>,
     pub killed_count: Option<u32>,
+    pub skipped: Option<Vec<SkippedProcess>>,
+    pub terminations: Option<Vec<TerminationResult>>,
+}
+
+impl ProcessResult {
+    fn error(message: String) -> Self {
+        ProcessResult {
+            success: false,
+            message,
+            processes: None,
+            killed_count: None,
+            skipped: None,
+            terminations: None,
+        }
+    }
+}
+
+// 进程扫描是同步且可能较慢的操作，放到阻塞线程池执行，避免占用 async 运行时
+async fn run_blocking<T, F>(f: F) -> Result<T, ProcessError>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, ProcessError> + Send + 'static,
+{
+    tauri::async_runtime::spawn_blocking(f)
+        .await
+        .map_err(|e| ProcessError::WorkerUnavailable(e.to_string()))?
+}
+
+fn to_matchers(names: Vec<String>, mode: Option<MatchMode>) -> Vec<ProcessMatcher> {
+    let mode = mode.unwrap_or_default();
+    names
+        .into_iter()
+        .map(|name| ProcessMatcher::new(mode, name))
+        .collect()
 }
 
 #[command]
-pub async fn check_processes(names: Vec<String>) -> Result<ProcessResult, String> {
-    refresh_system();
-    let running = get_running_apps(&names);
-    
+pub async fn check_processes(
+    state: State<'_, BlockerState>,
+    names: Vec<String>,
+    mode: Option<MatchMode>,
+) -> Result<ProcessResult, String> {
+    let matchers = to_matchers(names, mode);
+    let apps = state.apps();
+    let running = match run_blocking(move || apps.running(&matchers)).await {
+        Ok(running) => running,
+        Err(e) => return Ok(ProcessResult::error(e.to_string())),
+    };
+
     Ok(ProcessResult {
         success: true,
         message: format!("Found {} running processes", running.len()),
         processes: Some(running),
         killed_count: None,
+        skipped: None,
+        terminations: None,
     })
 }
 
+fn emit_terminations<R: Runtime>(app: &AppHandle<R>, results: &[TerminationResult]) {
+    for result in results {
+        let _ = app.emit("process-terminated", result.clone());
+    }
+}
+
 #[command]
-pub async fn kill_process_cmd(pid: u32) -> Result<ProcessResult, String> {
-    let success = kill_process(pid);
-    
-    Ok(ProcessResult {
-        success,
-        message: if success {
-            format!("Successfully killed process {}", pid)
-        } else {
-            format!("Failed to kill process {}", pid)
-        },
-        processes: None,
-        killed_count: if success { Some(1) } else { Some(0) },
+pub async fn kill_process_cmd<R: Runtime>(
+    app: AppHandle<R>,
+    state: State<'_, BlockerState>,
+    pid: u32,
+    options: Option<TerminateOptions>,
+) -> Result<ProcessResult, String> {
+    // 按 PID 结束时默认只结束该进程，需要连同子进程时由调用方显式传入 kill_tree
+    let options = options.unwrap_or(TerminateOptions {
+        kill_tree: false,
+        ..Default::default()
+    });
+    let apps = state.apps();
+    let result = run_blocking(move || {
+        apps.refresh()?;
+        apps.kill_pid(pid, &options)
     })
+    .await;
+
+    match result {
+        Ok(report) => {
+            emit_terminations(&app, &report.results);
+            Ok(ProcessResult {
+                success: true,
+                message: if report.killed > 1 {
+                    format!(
+                        "Successfully killed process {} and {} descendants",
+                        pid,
+                        report.killed - 1
+                    )
+                } else {
+                    format!("Successfully killed process {}", pid)
+                },
+                processes: None,
+                killed_count: Some(report.killed),
+                skipped: Some(report.skipped),
+                terminations: Some(report.results),
+            })
+        }
+        Err(ProcessError::Protected { pid, name, reason }) => Ok(ProcessResult {
+            success: false,
+            message: format!(
+                "Refusing to kill protected process {} ({}): {}",
+                name, pid, reason
+            ),
+            processes: None,
+            killed_count: Some(0),
+            skipped: Some(vec![SkippedProcess { pid, name, reason }]),
+            terminations: None,
+        }),
+        Err(e) => Ok(ProcessResult {
+            success: false,
+            message: e.to_string(),
+            processes: None,
+            killed_count: Some(0),
+            skipped: None,
+            terminations: None,
+        }),
+    }
 }
 
 #[command]
-pub async fn kill_app_processes(name: String) -> Result<ProcessResult, String> {
-    let count = kill_processes_by_name(&name);
-    
+pub async fn kill_app_processes<R: Runtime>(
+    app: AppHandle<R>,
+    state: State<'_, BlockerState>,
+    name: String,
+    mode: Option<MatchMode>,
+    options: Option<TerminateOptions>,
+) -> Result<ProcessResult, String> {
+    let matcher = ProcessMatcher::new(mode.unwrap_or_default(), name.clone());
+    let options = options.unwrap_or_default();
+    let apps = state.apps();
+    let result = run_blocking(move || {
+        apps.refresh()?;
+        apps.kill_matching(&[matcher], &options)
+    })
+    .await;
+    let report = match result {
+        Ok(report) => report,
+        Err(e) => return Ok(ProcessResult::error(e.to_string())),
+    };
+
+    emit_terminations(&app, &report.results);
+
+    let message = if report.skipped.is_empty() {
+        format!("Killed {} instances of {}", report.killed, name)
+    } else {
+        format!(
+            "Killed {} instances of {}, skipped {} protected processes",
+            report.killed,
+            name,
+            report.skipped.len()
+        )
+    };
+
     Ok(ProcessResult {
         success: true,
-        message: format!("Killed {} instances of {}", count, name),
+        message,
         processes: None,
-        killed_count: Some(count),
+        killed_count: Some(report.killed),
+        skipped: Some(report.skipped),
+        terminations: Some(report.results),
     })
 }
 
 #[command]
-pub async fn is_app_running_cmd(name: String) -> Result<ProcessResult, String> {
-    let running = is_app_running(&name);
-    
+pub async fn is_app_running_cmd(
+    state: State<'_, BlockerState>,
+    name: String,
+    mode: Option<MatchMode>,
+) -> Result<ProcessResult, String> {
+    let matcher = ProcessMatcher::new(mode.unwrap_or_default(), name.clone());
+    let apps = state.apps();
+    let result = run_blocking(move || {
+        apps.refresh()?;
+        Ok(!apps.find(&matcher)?.is_empty())
+    })
+    .await;
+    let running = match result {
+        Ok(running) => running,
+        Err(e) => return Ok(ProcessResult::error(e.to_string())),
+    };
+
     Ok(ProcessResult {
         success: true,
         message: if running {
@@ -67,17 +217,182 @@ pub async fn is_app_running_cmd(name: String) -> Result<ProcessResult, String> {
         },
         processes: None,
         killed_count: None,
+        skipped: None,
+        terminations: None,
     })
 }
 
 #[command]
-pub async fn find_processes_cmd(name: String) -> Result<ProcessResult, String> {
-    let processes = find_processes_by_name(&name);
-    
+pub async fn find_processes_cmd(
+    state: State<'_, BlockerState>,
+    name: String,
+    mode: Option<MatchMode>,
+) -> Result<ProcessResult, String> {
+    let matcher = ProcessMatcher::new(mode.unwrap_or_default(), name.clone());
+    let apps = state.apps();
+    let result = run_blocking(move || {
+        apps.refresh()?;
+        apps.find(&matcher)
+    })
+    .await;
+    let processes = match result {
+        Ok(processes) => processes,
+        Err(e) => return Ok(ProcessResult::error(e.to_string())),
+    };
+
     Ok(ProcessResult {
         success: true,
         message: format!("Found {} processes matching '{}'", processes.len(), name),
         processes: Some(processes),
         killed_count: None,
+        skipped: None,
+        terminations: None,
     })
 }
+
+#[command]
+pub async fn get_app_catalog(refresh: Option<bool>) -> Result<Vec<AppEntry>, String> {
+    let refresh = refresh.unwrap_or(false);
+    let catalog = tauri::async_runtime::spawn_blocking(move || get_catalog(refresh))
+        .await
+        .map_err(|e| e.to_string())?;
+    // 目录刷新后应用 id 可能展开成不同的进程名
+    if refresh {
+        invalidate_distracting_matchers();
+    }
+    Ok(catalog)
+}
+
+#[command]
+pub async fn check_apps(
+    state: State<'_, BlockerState>,
+    app_ids: Vec<String>,
+) -> Result<ProcessResult, String> {
+    let apps = state.apps();
+    let result = run_blocking(move || {
+        let matchers = expand_app_ids(&app_ids)?;
+        apps.running(&matchers)
+    })
+    .await;
+    let running = match result {
+        Ok(running) => running,
+        Err(e) => return Ok(ProcessResult::error(e.to_string())),
+    };
+
+    Ok(ProcessResult {
+        success: true,
+        message: format!("Found {} running processes", running.len()),
+        processes: Some(running),
+        killed_count: None,
+        skipped: None,
+        terminations: None,
+    })
+}
+
+#[command]
+pub async fn kill_apps<R: Runtime>(
+    app: AppHandle<R>,
+    state: State<'_, BlockerState>,
+    app_ids: Vec<String>,
+    options: Option<TerminateOptions>,
+) -> Result<ProcessResult, String> {
+    let options = options.unwrap_or_default();
+    let count = app_ids.len();
+    let apps = state.apps();
+    let result = run_blocking(move || {
+        apps.refresh()?;
+        apps.kill_matching(&expand_app_ids(&app_ids)?, &options)
+    })
+    .await;
+    let report = match result {
+        Ok(report) => report,
+        Err(e) => return Ok(ProcessResult::error(e.to_string())),
+    };
+
+    emit_terminations(&app, &report.results);
+
+    Ok(ProcessResult {
+        success: true,
+        message: format!(
+            "Killed {} processes belonging to {} apps",
+            report.killed, count
+        ),
+        processes: None,
+        killed_count: Some(report.killed),
+        skipped: Some(report.skipped),
+        terminations: Some(report.results),
+    })
+}
+
+#[command]
+pub async fn get_process_allowlist() -> Result<Vec<String>, String> {
+    Ok(get_protected_allowlist())
+}
+
+#[command]
+pub async fn set_process_allowlist(names: Vec<String>) -> Result<Vec<String>, String> {
+    // 锁定期间只能缩小名单，否则可以借此保护被拦截的应用
+    if is_currently_locked() && loosens_protected_allowlist(&names) {
+        return Err("当前处于锁定模式，无法添加受保护的进程".to_string());
+    }
+    set_protected_allowlist(&names).map_err(|e| e.to_string())?;
+    Ok(get_protected_allowlist())
+}
+
+fn emit_process_event(app: &AppHandle, event: ProcessEvent) {
+    let name = match &event {
+        ProcessEvent::Started { .. } => "process-started",
+        ProcessEvent::Exited { .. } => "process-exited",
+        ProcessEvent::Blocked { process, .. } => {
+            if let Err(e) = record_block(BlockKind::App, &process.name) {
+                log::warn!("failed to record block of {}: {}", process.name, e);
+            }
+            "process-blocked"
+        }
+    };
+    let _ = app.emit(name, event);
+}
+
+#[command]
+pub async fn start_process_watch(
+    app: AppHandle,
+    options: Option<WatchOptions>,
+) -> Result<WatchStatus, String> {
+    let options = options.unwrap_or_default();
+    // 重新启动会替换正在运行的监控的选项
+    let status = get_watch_status();
+    if status.running && is_currently_locked() && status.options.loosened_by(&options) {
+        return Err("当前处于锁定模式，无法放宽应用拦截".to_string());
+    }
+    let started = start_watch(options, move |event| {
+        emit_process_event(&app, event)
+    });
+    invalidate_distracting_matchers();
+    started.map_err(|e| e.to_string())?;
+    Ok(get_watch_status())
+}
+
+#[command]
+pub async fn update_process_watch(options: WatchOptions) -> Result<WatchStatus, String> {
+    if is_currently_locked() && get_watch_status().options.loosened_by(&options) {
+        return Err("当前处于锁定模式，无法放宽应用拦截".to_string());
+    }
+    let updated = update_watch_options(options);
+    invalidate_distracting_matchers();
+    updated.map_err(|e| e.to_string())?;
+    Ok(get_watch_status())
+}
+
+#[command]
+pub async fn stop_process_watch() -> Result<WatchStatus, String> {
+    if is_currently_locked() && get_watch_status().running {
+        return Err("当前处于锁定模式，无法停止应用拦截".to_string());
+    }
+    stop_watch();
+    Ok(get_watch_status())
+}
+
+#[command]
+pub async fn get_process_watch_status() -> Result<WatchStatus, String> {
+    Ok(get_watch_status())
+}
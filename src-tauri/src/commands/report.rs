@@ -0,0 +1,409 @@
+use crate::commands::goals::{goal_progress, goals};
+use crate::commands::stats::{
+    format_duration, load_history, top_blocked_targets, AppUsage, BlockKind, BlockedTarget, DateRange, StatsData,
+    HISTORY_RETENTION_DAYS,
+};
+use chrono::{Datelike, Duration, Local, Months, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::fs;
+use std::path::PathBuf;
+use tauri::command;
+
+const TOP_APPS: usize = 10;
+const TOP_BLOCKED: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportPeriod {
+    /// Monday to Sunday.
+    Week,
+    Month,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportFormat {
+    #[default]
+    Markdown,
+    Html,
+    Json,
+}
+
+impl ReportFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            ReportFormat::Markdown => "md",
+            ReportFormat::Html => "html",
+            ReportFormat::Json => "json",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReportDay {
+    pub date: String,
+    pub focus_seconds: u64,
+    pub sessions_completed: u32,
+    pub sessions_abandoned: u32,
+    /// Foreground time in blocked or budgeted apps.
+    pub distraction_seconds: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Report {
+    pub period: ReportPeriod,
+    /// First and last day covered, inclusive.
+    pub start: String,
+    pub end: String,
+    pub generated_at: String,
+    pub total_focus_seconds: u64,
+    pub average_daily_focus_seconds: u64,
+    pub sessions_completed: u32,
+    pub sessions_abandoned: u32,
+    pub idle_seconds: u64,
+    pub distraction_seconds: u64,
+    /// Days in the period on which the daily goal was met.
+    pub goal_days_met: u32,
+    pub current_streak: u32,
+    pub longest_streak: u32,
+    pub days: Vec<ReportDay>,
+    /// Blocked or budgeted apps by foreground time, longest first.
+    pub top_distractions: Vec<AppUsage>,
+    /// Most blocked sites and apps, most frequent first.
+    pub top_blocked: Vec<BlockedTarget>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReportResult {
+    pub success: bool,
+    pub message: String,
+    pub content: Option<String>,
+    /// Where the report was written, when an output directory was given.
+    pub path: Option<String>,
+}
+
+/// First and last day of the period `offset` periods before the current one.
+/// Fails when the period starts before the retained history.
+fn period_range(period: ReportPeriod, offset: u32, today: NaiveDate) -> Result<(NaiveDate, NaiveDate), String> {
+    let range = match period {
+        ReportPeriod::Week => {
+            let monday = today - Duration::days(i64::from(today.weekday().num_days_from_monday()));
+            monday
+                .checked_sub_signed(Duration::weeks(i64::from(offset)))
+                .map(|start| (start, start + Duration::days(6)))
+        }
+        ReportPeriod::Month => {
+            let first = today.with_day(1).unwrap_or(today);
+            first.checked_sub_months(Months::new(offset)).and_then(|start| {
+                let end = start.checked_add_months(Months::new(1))? - Duration::days(1);
+                Some((start, end))
+            })
+        }
+    };
+    let (start, end) = range.ok_or_else(|| format!("Report offset {} is out of range", offset))?;
+
+    // 超出保留期的历史已被清理，报告只会是空的
+    if start < today - Duration::days(HISTORY_RETENTION_DAYS) {
+        return Err(format!(
+            "History is only kept for {} days; no data for the period starting {}",
+            HISTORY_RETENTION_DAYS, start
+        ));
+    }
+    Ok((start, end))
+}
+
+fn to_date_range(start: NaiveDate, end: NaiveDate) -> DateRange {
+    DateRange {
+        from: Some(start.format("%Y-%m-%d").to_string()),
+        to: Some(end.format("%Y-%m-%d").to_string()),
+    }
+}
+
+/// Builds the report for `start..=end` from `stats`, which must hold the history of that range.
+pub fn build_report(stats: &StatsData, period: ReportPeriod, start: NaiveDate, end: NaiveDate) -> Result<Report, String> {
+    let today = Local::now().date_naive();
+    let daily_goal = goals().daily_minutes * 60;
+
+    let mut days: BTreeMap<String, ReportDay> = start
+        .iter_days()
+        .take_while(|date| *date <= end)
+        .map(|date| {
+            let date = date.format("%Y-%m-%d").to_string();
+            let day = ReportDay {
+                date: date.clone(),
+                focus_seconds: stats.daily_focus.get(&date).copied().unwrap_or(0),
+                ..Default::default()
+            };
+            (date, day)
+        })
+        .collect();
+
+    let mut idle_seconds = 0;
+    for session in &stats.sessions {
+        let Some(day) = session.started_at.get(..10).and_then(|date| days.get_mut(date)) else {
+            continue;
+        };
+        if session.completed {
+            day.sessions_completed += 1;
+        } else {
+            day.sessions_abandoned += 1;
+        }
+        idle_seconds += session.idle_seconds;
+    }
+
+    let mut distractions: BTreeMap<String, AppUsage> = BTreeMap::new();
+    for (date, apps) in &stats.app_usage {
+        let Some(day) = days.get_mut(date) else {
+            continue;
+        };
+        for (key, usage) in apps.iter().filter(|(_, usage)| usage.distracting) {
+            day.distraction_seconds += usage.seconds;
+            let entry = distractions.entry(key.clone()).or_insert_with(|| AppUsage {
+                name: usage.name.clone(),
+                exe: usage.exe.clone(),
+                distracting: true,
+                ..Default::default()
+            });
+            entry.seconds += usage.seconds;
+        }
+    }
+    let mut top_distractions: Vec<AppUsage> = distractions.into_values().collect();
+    top_distractions.sort_by_key(|a| std::cmp::Reverse(a.seconds));
+    top_distractions.truncate(TOP_APPS);
+
+    let days: Vec<ReportDay> = days.into_values().collect();
+    let total_focus_seconds: u64 = days.iter().map(|d| d.focus_seconds).sum();
+    // 本期尚未结束时按已经过的天数求平均
+    let elapsed_days = days.iter().filter(|d| d.date <= today.format("%Y-%m-%d").to_string()).count().max(1);
+    let progress = goal_progress(stats);
+    let top_blocked = top_blocked_targets(&to_date_range(start, end), TOP_BLOCKED).map_err(|e| e.to_string())?;
+
+    Ok(Report {
+        period,
+        start: start.format("%Y-%m-%d").to_string(),
+        end: end.format("%Y-%m-%d").to_string(),
+        generated_at: Local::now().to_rfc3339(),
+        total_focus_seconds,
+        average_daily_focus_seconds: total_focus_seconds / elapsed_days as u64,
+        sessions_completed: days.iter().map(|d| d.sessions_completed).sum(),
+        sessions_abandoned: days.iter().map(|d| d.sessions_abandoned).sum(),
+        idle_seconds,
+        distraction_seconds: days.iter().map(|d| d.distraction_seconds).sum(),
+        goal_days_met: days
+            .iter()
+            .filter(|d| daily_goal > 0 && d.focus_seconds >= daily_goal)
+            .count() as u32,
+        current_streak: progress.current_streak,
+        longest_streak: progress.longest_streak,
+        days,
+        top_distractions,
+        top_blocked,
+    })
+}
+
+fn title(report: &Report) -> String {
+    let kind = match report.period {
+        ReportPeriod::Week => "专注周报",
+        ReportPeriod::Month => "专注月报",
+    };
+    format!("{} {} ~ {}", kind, report.start, report.end)
+}
+
+fn kind_label(kind: BlockKind) -> &'static str {
+    match kind {
+        BlockKind::Website => "网站",
+        BlockKind::App => "应用",
+    }
+}
+
+fn summary_rows(report: &Report) -> Vec<(&'static str, String)> {
+    vec![
+        ("总专注时长", format_duration(report.total_focus_seconds)),
+        ("日均专注", format_duration(report.average_daily_focus_seconds)),
+        ("完成的专注", report.sessions_completed.to_string()),
+        ("中途放弃", report.sessions_abandoned.to_string()),
+        ("离开时长", format_duration(report.idle_seconds)),
+        ("分心应用时长", format_duration(report.distraction_seconds)),
+        ("达成每日目标", format!("{} 天", report.goal_days_met)),
+        ("当前连胜", format!("{} 天", report.current_streak)),
+        ("最长连胜", format!("{} 天", report.longest_streak)),
+    ]
+}
+
+fn render_markdown(report: &Report) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "# {}\n", title(report));
+    let _ = writeln!(out, "## 概览\n");
+    for (label, value) in summary_rows(report) {
+        let _ = writeln!(out, "- {}：{}", label, value);
+    }
+
+    let _ = writeln!(out, "\n## 每日明细\n");
+    let _ = writeln!(out, "| 日期 | 专注 | 完成 | 放弃 | 分心 |");
+    let _ = writeln!(out, "| --- | --- | --- | --- | --- |");
+    for day in &report.days {
+        let _ = writeln!(
+            out,
+            "| {} | {} | {} | {} | {} |",
+            day.date,
+            format_duration(day.focus_seconds),
+            day.sessions_completed,
+            day.sessions_abandoned,
+            format_duration(day.distraction_seconds)
+        );
+    }
+
+    let _ = writeln!(out, "\n## 最常使用的分心应用\n");
+    if report.top_distractions.is_empty() {
+        let _ = writeln!(out, "无");
+    }
+    for (index, app) in report.top_distractions.iter().enumerate() {
+        let _ = writeln!(out, "{}. {} — {}", index + 1, app.name.replace('|', "\\|"), format_duration(app.seconds));
+    }
+
+    let _ = writeln!(out, "\n## 拦截最多的目标\n");
+    if report.top_blocked.is_empty() {
+        let _ = writeln!(out, "无");
+    }
+    for (index, blocked) in report.top_blocked.iter().enumerate() {
+        let _ = writeln!(out, "{}. {}（{}）— {} 次", index + 1, blocked.target, kind_label(blocked.kind), blocked.count);
+    }
+    out
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn render_html(report: &Report) -> String {
+    let title = escape_html(&title(report));
+    let mut out = String::new();
+    let _ = writeln!(out, "<!DOCTYPE html>\n<html lang=\"zh-CN\">\n<head>\n<meta charset=\"utf-8\">");
+    let _ = writeln!(out, "<title>{}</title>", title);
+    let _ = writeln!(
+        out,
+        "<style>body{{font-family:sans-serif;max-width:720px;margin:2em auto;color:#111827}}\
+         table{{border-collapse:collapse;width:100%}}td,th{{border:1px solid #e5e7eb;padding:4px 8px;text-align:left}}</style>"
+    );
+    let _ = writeln!(out, "</head>\n<body>\n<h1>{}</h1>", title);
+
+    let _ = writeln!(out, "<h2>概览</h2>\n<ul>");
+    for (label, value) in summary_rows(report) {
+        let _ = writeln!(out, "<li>{}：{}</li>", label, escape_html(&value));
+    }
+    let _ = writeln!(out, "</ul>");
+
+    let _ = writeln!(out, "<h2>每日明细</h2>\n<table>");
+    let _ = writeln!(out, "<tr><th>日期</th><th>专注</th><th>完成</th><th>放弃</th><th>分心</th></tr>");
+    for day in &report.days {
+        let _ = writeln!(
+            out,
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            day.date,
+            format_duration(day.focus_seconds),
+            day.sessions_completed,
+            day.sessions_abandoned,
+            format_duration(day.distraction_seconds)
+        );
+    }
+    let _ = writeln!(out, "</table>");
+
+    let _ = writeln!(out, "<h2>最常使用的分心应用</h2>");
+    if report.top_distractions.is_empty() {
+        let _ = writeln!(out, "<p>无</p>");
+    } else {
+        let _ = writeln!(out, "<ol>");
+        for app in &report.top_distractions {
+            let _ = writeln!(out, "<li>{} — {}</li>", escape_html(&app.name), format_duration(app.seconds));
+        }
+        let _ = writeln!(out, "</ol>");
+    }
+
+    let _ = writeln!(out, "<h2>拦截最多的目标</h2>");
+    if report.top_blocked.is_empty() {
+        let _ = writeln!(out, "<p>无</p>");
+    } else {
+        let _ = writeln!(out, "<ol>");
+        for blocked in &report.top_blocked {
+            let _ = writeln!(
+                out,
+                "<li>{}（{}）— {} 次</li>",
+                escape_html(&blocked.target),
+                kind_label(blocked.kind),
+                blocked.count
+            );
+        }
+        let _ = writeln!(out, "</ol>");
+    }
+    let _ = writeln!(out, "</body>\n</html>");
+    out
+}
+
+pub fn render_report(report: &Report, format: ReportFormat) -> Result<String, String> {
+    match format {
+        ReportFormat::Markdown => Ok(render_markdown(report)),
+        ReportFormat::Html => Ok(render_html(report)),
+        ReportFormat::Json => serde_json::to_string_pretty(report).map_err(|e| e.to_string()),
+    }
+}
+
+/// Builds the report for the current period (`offset` 0) or one `offset` periods back,
+/// and writes it into `output_dir` when given.
+#[command]
+pub async fn generate_report(
+    period: ReportPeriod,
+    offset: Option<u32>,
+    format: Option<ReportFormat>,
+    output_dir: Option<String>,
+) -> Result<ReportResult, String> {
+    let format = format.unwrap_or_default();
+    let (start, end) = match period_range(period, offset.unwrap_or(0), Local::now().date_naive()) {
+        Ok(range) => range,
+        Err(message) => {
+            return Ok(ReportResult {
+                success: false,
+                message,
+                content: None,
+                path: None,
+            })
+        }
+    };
+    let stats = load_history(&to_date_range(start, end)).map_err(|e| e.to_string())?;
+    let report = build_report(&stats, period, start, end)?;
+    let content = render_report(&report, format)?;
+
+    let Some(dir) = output_dir else {
+        return Ok(ReportResult {
+            success: true,
+            message: title(&report),
+            content: Some(content),
+            path: None,
+        });
+    };
+
+    let kind = match period {
+        ReportPeriod::Week => "week",
+        ReportPeriod::Month => "month",
+    };
+    let path = PathBuf::from(dir).join(format!("focus-report-{}-{}.{}", kind, report.start, format.extension()));
+    if let Err(e) = fs::write(&path, &content) {
+        return Ok(ReportResult {
+            success: false,
+            message: format!("Failed to write {}: {}", path.display(), e),
+            content: Some(content),
+            path: None,
+        });
+    }
+
+    Ok(ReportResult {
+        success: true,
+        message: format!("Report written to {}", path.display()),
+        content: Some(content),
+        path: Some(path.display().to_string()),
+    })
+}
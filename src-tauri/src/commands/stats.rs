@@ -1,13 +1,13 @@
-use chrono::{DateTime, Local, Datelike};
+use chrono::Local;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::PathBuf;
-use once_cell::sync::Lazy;
-use std::sync::Mutex;
+use crate::commands::focus::current_session_id;
+use crate::commands::lock::is_currently_locked;
+use crate::commands::stats_store::{self, StoreError};
 use tauri::command;
 
-static STATS_FILE: Lazy<Mutex<Option<PathBuf>>> = Lazy::new(|| Mutex::new(None));
-
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DailyStats {
     pub date: String,
@@ -15,117 +15,648 @@ pub struct DailyStats {
     pub block_count: u64,
     pub websites_blocked: u64,
     pub apps_blocked: u64,
+    #[serde(default)]
+    pub temporary_unblocks: u64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct StatsData {
     pub today: DailyStats,
     pub total_focus_seconds: u64,
     pub total_blocks: u64,
+    #[serde(default)]
+    pub total_temporary_unblocks: u64,
+    #[serde(default)]
+    pub budget_usage: BudgetUsage,
+    /// Foreground time per date, then per application. Like `sessions` and
+    /// `block_events`, only filled by `load_history`.
+    #[serde(default)]
+    pub app_usage: BTreeMap<String, BTreeMap<String, AppUsage>>,
+    #[serde(default)]
+    pub sessions: Vec<FocusSession>,
+    #[serde(default)]
+    pub total_sessions_completed: u64,
+    /// Focus seconds per date, kept for streaks and goals.
+    #[serde(default)]
+    pub daily_focus: BTreeMap<String, u64>,
+    #[serde(default)]
+    pub block_events: Vec<BlockEvent>,
+}
+
+/// Time used against daily budgets. Kept apart from `today` because budget days
+/// can start at a configured hour instead of midnight.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BudgetUsage {
+    pub day: String,
+    /// Seconds used per budget id.
+    pub seconds: BTreeMap<String, u64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AppUsage {
+    pub name: String,
+    pub exe: Option<String>,
+    pub seconds: u64,
+    /// The app was blocked or had a usage budget while it was in the foreground.
+    pub distracting: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DailyAppUsage {
+    pub date: String,
+    pub total_seconds: u64,
+    pub distraction_seconds: u64,
+    /// Sorted by time spent, longest first.
+    pub apps: Vec<AppUsage>,
+}
+
+/// A finished focus session.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FocusSession {
+    #[serde(default)]
+    pub id: String,
+    /// RFC 3339 local time.
+    pub started_at: String,
+    pub ended_at: String,
+    pub target_seconds: u64,
+    /// Time counted towards the target.
+    pub focus_seconds: u64,
+    /// Time the session was paused because there was no input.
+    pub idle_seconds: u64,
+    pub idle_periods: u32,
+    /// Time the system was suspended during the session.
+    #[serde(default)]
+    pub suspended_seconds: u64,
+    pub completed: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BlockKind {
+    Website,
+    App,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockEvent {
+    /// RFC 3339 local time.
+    pub at: String,
+    pub kind: BlockKind,
+    /// Domain or process name; empty for events recorded before targets were kept.
+    #[serde(default)]
+    pub target: String,
+    /// Id of the focus session running at the time, if any.
+    #[serde(default)]
+    pub session_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockedTarget {
+    pub target: String,
+    pub kind: BlockKind,
+    pub count: u64,
+    pub last_at: String,
+}
+
+/// Block counts by weekday (Monday first) and local hour.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockHeatmap {
+    pub cells: Vec<Vec<u64>>,
+    pub max: u64,
+    pub total: u64,
+}
+
+pub(crate) const HISTORY_RETENTION_DAYS: i64 = 90;
+
+fn retention_cutoff() -> String {
+    (Local::now() - chrono::Duration::days(HISTORY_RETENTION_DAYS))
+        .format("%Y-%m-%d")
+        .to_string()
+}
+
+fn today() -> String {
+    Local::now().format("%Y-%m-%d").to_string()
 }
 
 impl Default for DailyStats {
     fn default() -> Self {
         DailyStats {
-            date: Local::now().format("%Y-%m-%d").to_string(),
+            date: today(),
             focus_duration_seconds: 0,
             block_count: 0,
             websites_blocked: 0,
             apps_blocked: 0,
+            temporary_unblocks: 0,
         }
     }
 }
 
-fn get_stats_path() -> PathBuf {
-    if let Some(path) = STATS_FILE.lock().unwrap().clone() {
-        return path;
+/// Today's numbers, totals, per-day focus and budget usage; history lists stay empty.
+pub fn load_stats() -> StatsData {
+    stats_store::load_summary().unwrap_or_else(|e| {
+        log::error!("failed to load stats: {}", e);
+        StatsData::default()
+    })
+}
+
+/// Like `load_stats`, plus the sessions, block events and app usage within `range`.
+pub fn load_history(range: &DateRange) -> Result<StatsData, StoreError> {
+    let mut data = stats_store::load_summary()?;
+    data.sessions = stats_store::sessions(range)?;
+    data.block_events = stats_store::block_events(range)?;
+    data.app_usage = stats_store::app_usage(range)?;
+    Ok(data)
+}
+
+#[command]
+pub async fn get_stats() -> Result<StatsData, String> {
+    stats_store::load_summary().map_err(|e| e.to_string())
+}
+
+pub fn add_focus_seconds(seconds: u64) -> Result<StatsData, StoreError> {
+    stats_store::add_focus_seconds(&today(), seconds)?;
+    stats_store::load_summary()
+}
+
+#[command]
+pub async fn add_focus_time(seconds: u64) -> Result<StatsData, String> {
+    add_focus_seconds(seconds).map_err(|e| e.to_string())
+}
+
+/// Records one block of `target` and attributes it to the running focus session.
+pub fn record_block(kind: BlockKind, target: &str) -> Result<StatsData, StoreError> {
+    let event = BlockEvent {
+        at: Local::now().to_rfc3339(),
+        kind,
+        target: target.trim().to_lowercase(),
+        session_id: current_session_id(),
+    };
+    stats_store::record_block(&event, &retention_cutoff())?;
+    stats_store::load_summary()
+}
+
+#[command]
+pub async fn add_block(website: bool, target: Option<String>) -> Result<StatsData, String> {
+    let kind = if website { BlockKind::Website } else { BlockKind::App };
+    record_block(kind, target.as_deref().unwrap_or_default()).map_err(|e| e.to_string())
+}
+
+#[command]
+pub async fn reset_stats() -> Result<StatsData, String> {
+    if is_currently_locked() {
+        return Err("当前处于锁定模式，无法重置统计".to_string());
     }
+    stats_store::clear().map_err(|e| e.to_string())?;
+    Ok(StatsData::default())
+}
+
+#[command]
+pub async fn get_focus_duration() -> Result<u64, String> {
+    Ok(load_stats().today.focus_duration_seconds)
+}
+
+#[command]
+pub async fn get_block_count() -> Result<u64, String> {
+    Ok(load_stats().today.block_count)
+}
+
+pub fn record_temporary_unblock() -> Result<(), StoreError> {
+    stats_store::record_temporary_unblock(&today())
+}
 
-    let path = dirs::data_local_dir()
-        .unwrap_or_else(|| PathBuf::from("."))
-        .join("FocusKeeper")
-        .join("stats.json");
+/// Seconds used per budget id on budget day `day`.
+pub fn get_budget_usage(day: &str) -> BTreeMap<String, u64> {
+    stats_store::budget_usage(day).unwrap_or_else(|e| {
+        log::error!("failed to load budget usage: {}", e);
+        BTreeMap::new()
+    })
+}
 
-    if let Some(parent) = path.parent() {
-        let _ = fs::create_dir_all(parent);
+/// Adds `seconds` to each budget in `ids`, starting from zero when `day` is a new
+/// budget day. Returns the usage for `day` after the update.
+pub fn add_budget_usage(day: &str, ids: &[String], seconds: u64) -> Result<BTreeMap<String, u64>, StoreError> {
+    stats_store::add_budget_usage(day, ids, seconds)
+}
+
+/// Merges foreground samples for `date` into the stored usage, dropping dates past the retention window.
+pub fn add_app_usage(date: &str, usage: &[AppUsage]) -> Result<(), StoreError> {
+    stats_store::add_app_usage(date, usage, &retention_cutoff())
+}
+
+fn daily_app_usage(date: String, apps: Option<&BTreeMap<String, AppUsage>>) -> DailyAppUsage {
+    let mut apps: Vec<AppUsage> = apps.map(|apps| apps.values().cloned().collect()).unwrap_or_default();
+    apps.sort_by_key(|a| std::cmp::Reverse(a.seconds));
+    DailyAppUsage {
+        date,
+        total_seconds: apps.iter().map(|a| a.seconds).sum(),
+        distraction_seconds: apps.iter().filter(|a| a.distracting).map(|a| a.seconds).sum(),
+        apps,
     }
+}
 
-    *STATS_FILE.lock().unwrap() = Some(path.clone());
-    path
+#[command]
+pub async fn get_app_usage(date: Option<String>) -> Result<DailyAppUsage, String> {
+    let date = date.unwrap_or_else(today);
+    let usage = stats_store::app_usage(&DateRange::day(&date)).map_err(|e| e.to_string())?;
+    let apps = usage.get(&date);
+    Ok(daily_app_usage(date, apps))
 }
 
-fn load_stats() -> StatsData {
-    let path = get_stats_path();
-    
-    if let Ok(content) = fs::read_to_string(&path) {
-        if let Ok(data) = serde_json::from_str::<StatsData>(&content) {
-            let today = Local::now().format("%Y-%m-%d").to_string();
-            if data.today.date == today {
-                return data;
+/// Usage for the last `days` days including today, oldest first. Days without
+/// samples are included with zero totals so the result can be charted directly.
+#[command]
+pub async fn get_app_usage_history(days: Option<u32>) -> Result<Vec<DailyAppUsage>, String> {
+    let days = i64::from(days.unwrap_or(7).clamp(1, HISTORY_RETENTION_DAYS as u32));
+    let today = Local::now();
+    let dates: Vec<String> = (0..days)
+        .rev()
+        .map(|offset| (today - chrono::Duration::days(offset)).format("%Y-%m-%d").to_string())
+        .collect();
+    let range = DateRange {
+        from: dates.first().cloned(),
+        to: dates.last().cloned(),
+    };
+    let usage = stats_store::app_usage(&range).map_err(|e| e.to_string())?;
+    Ok(dates
+        .into_iter()
+        .map(|date| {
+            let apps = usage.get(&date);
+            daily_app_usage(date, apps)
+        })
+        .collect())
+}
+
+pub fn record_focus_session(session: FocusSession) -> Result<(), StoreError> {
+    stats_store::record_session(&session, &retention_cutoff())
+}
+
+#[command]
+pub async fn get_focus_sessions(date: Option<String>) -> Result<Vec<FocusSession>, String> {
+    let range = date.as_deref().map(DateRange::day).unwrap_or_default();
+    stats_store::sessions(&range).map_err(|e| e.to_string())
+}
+
+/// Inclusive `YYYY-MM-DD` bounds; a missing bound is open.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DateRange {
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+impl DateRange {
+    pub fn day(date: &str) -> Self {
+        DateRange {
+            from: Some(date.to_string()),
+            to: Some(date.to_string()),
+        }
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        for date in [&self.from, &self.to].into_iter().flatten() {
+            chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                .map_err(|_| format!("Invalid date '{}', expected YYYY-MM-DD", date))?;
+        }
+        if let (Some(from), Some(to)) = (&self.from, &self.to) {
+            if from > to {
+                return Err(format!("Range start {} is after its end {}", from, to));
             }
         }
+        Ok(())
     }
 
-    StatsData {
-        today: DailyStats::default(),
-        total_focus_seconds: 0,
-        total_blocks: 0,
+    // 时间戳以 RFC 3339 存储，前 10 个字符就是本地日期
+    fn contains(&self, timestamp: &str) -> bool {
+        let Some(date) = timestamp.get(..10) else {
+            return false;
+        };
+        self.from.as_deref().map(|from| date >= from).unwrap_or(true)
+            && self.to.as_deref().map(|to| date <= to).unwrap_or(true)
     }
 }
 
-fn save_stats(data: &StatsData) -> std::io::Result<()> {
-    let path = get_stats_path();
-    let content = serde_json::to_string_pretty(data)?;
-    fs::write(path, content)
+pub fn top_blocked_targets(range: &DateRange, n: usize) -> Result<Vec<BlockedTarget>, StoreError> {
+    stats_store::top_blocked(range, n)
 }
 
+/// Most blocked sites and apps in `range`, most frequent first.
 #[command]
-pub async fn get_stats() -> Result<StatsData, String> {
-    Ok(load_stats())
+pub async fn top_blocked(range: Option<DateRange>, n: Option<u32>) -> Result<Vec<BlockedTarget>, String> {
+    let range = range.unwrap_or_default();
+    range.validate()?;
+    top_blocked_targets(&range, n.unwrap_or(10) as usize).map_err(|e| e.to_string())
 }
 
+/// Block counts by weekday and hour, in the local time each block was recorded at.
 #[command]
-pub async fn add_focus_time(seconds: u64) -> Result<StatsData, String> {
-    let mut data = load_stats();
-    data.today.focus_duration_seconds += seconds;
-    data.total_focus_seconds += seconds;
-    save_stats(&data).map_err(|e| e.to_string())?;
-    Ok(data)
+pub async fn get_block_heatmap(range: Option<DateRange>) -> Result<BlockHeatmap, String> {
+    let range = range.unwrap_or_default();
+    range.validate()?;
+    stats_store::block_heatmap(&range).map_err(|e| e.to_string())
 }
 
-#[command]
-pub async fn add_block(website: bool) -> Result<StatsData, String> {
-    let mut data = load_stats();
-    data.today.block_count += 1;
-    data.total_blocks += 1;
-    if website {
-        data.today.websites_blocked += 1;
+/// Bumped whenever a column is added, removed or changes meaning, so scripts
+/// reading exports can tell which layout they got.
+pub const EXPORT_SCHEMA_VERSION: u32 = 2;
+
+const DAILY_COLUMNS: &[&str] = &[
+    "schema_version",
+    "date",
+    "focus_seconds",
+    "sessions_completed",
+    "sessions_abandoned",
+    "idle_seconds",
+    "blocks",
+    "websites_blocked",
+    "apps_blocked",
+    "app_seconds",
+    "distraction_seconds",
+];
+
+const SESSION_COLUMNS: &[&str] = &[
+    "schema_version",
+    "id",
+    "started_at",
+    "ended_at",
+    "target_seconds",
+    "focus_seconds",
+    "idle_seconds",
+    "idle_periods",
+    "suspended_seconds",
+    "completed",
+];
+
+const BLOCK_COLUMNS: &[&str] = &["schema_version", "at", "kind", "target", "session_id"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    Csv,
+    Jsonl,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportFile {
+    /// `daily`, `sessions` or `blocks`.
+    pub dataset: String,
+    pub file_name: String,
+    pub rows: usize,
+    pub content: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExportResult {
+    pub success: bool,
+    pub message: String,
+    pub schema_version: u32,
+    pub files: Vec<ExportFile>,
+    /// Directory the files were written to, when one was given.
+    pub path: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+struct DailyRow {
+    schema_version: u32,
+    date: String,
+    focus_seconds: u64,
+    sessions_completed: u32,
+    sessions_abandoned: u32,
+    idle_seconds: u64,
+    blocks: u64,
+    websites_blocked: u64,
+    apps_blocked: u64,
+    app_seconds: u64,
+    distraction_seconds: u64,
+}
+
+fn daily_row<'a>(days: &'a mut BTreeMap<String, DailyRow>, range: &DateRange, timestamp: &str) -> Option<&'a mut DailyRow> {
+    if !range.contains(timestamp) {
+        return None;
+    }
+    let date = timestamp.get(..10)?;
+    Some(days.entry(date.to_string()).or_insert_with(|| DailyRow {
+        schema_version: EXPORT_SCHEMA_VERSION,
+        date: date.to_string(),
+        ..Default::default()
+    }))
+}
+
+// 各类数据的日期不一定重合，按日期汇总到同一行
+fn daily_rows(data: &StatsData, range: &DateRange) -> Vec<DailyRow> {
+    let mut days: BTreeMap<String, DailyRow> = BTreeMap::new();
+
+    for (date, seconds) in &data.daily_focus {
+        if let Some(row) = daily_row(&mut days, range, date) {
+            row.focus_seconds += seconds;
+        }
+    }
+    for session in &data.sessions {
+        if let Some(row) = daily_row(&mut days, range, &session.started_at) {
+            if session.completed {
+                row.sessions_completed += 1;
+            } else {
+                row.sessions_abandoned += 1;
+            }
+            row.idle_seconds += session.idle_seconds;
+        }
+    }
+    for event in &data.block_events {
+        if let Some(row) = daily_row(&mut days, range, &event.at) {
+            row.blocks += 1;
+            match event.kind {
+                BlockKind::Website => row.websites_blocked += 1,
+                BlockKind::App => row.apps_blocked += 1,
+            }
+        }
+    }
+    for (date, apps) in &data.app_usage {
+        if let Some(row) = daily_row(&mut days, range, date) {
+            for usage in apps.values() {
+                row.app_seconds += usage.seconds;
+                if usage.distracting {
+                    row.distraction_seconds += usage.seconds;
+                }
+            }
+        }
+    }
+    days.into_values().collect()
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
     } else {
-        data.today.apps_blocked += 1;
+        value.to_string()
     }
-    save_stats(&data).map_err(|e| e.to_string())?;
-    Ok(data)
 }
 
-#[command]
-pub async fn reset_stats() -> Result<StatsData, String> {
-    let data = StatsData {
-        today: DailyStats::default(),
-        total_focus_seconds: 0,
-        total_blocks: 0,
-    };
-    save_stats(&data).map_err(|e| e.to_string())?;
-    Ok(data)
+fn csv_line(fields: &[String]) -> String {
+    let fields: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+    fields.join(",") + "\n"
 }
 
-#[command]
-pub async fn get_focus_duration() -> Result<u64, String> {
-    Ok(load_stats().today.focus_duration_seconds)
+fn render_export<T: Serialize>(
+    format: ExportFormat,
+    columns: &[&str],
+    rows: &[T],
+    to_fields: impl Fn(&T) -> Vec<String>,
+) -> Result<String, String> {
+    let mut out = String::new();
+    match format {
+        ExportFormat::Csv => {
+            out.push_str(&csv_line(&columns.iter().map(|c| c.to_string()).collect::<Vec<_>>()));
+            for row in rows {
+                out.push_str(&csv_line(&to_fields(row)));
+            }
+        }
+        ExportFormat::Jsonl => {
+            for row in rows {
+                out.push_str(&serde_json::to_string(row).map_err(|e| e.to_string())?);
+                out.push('\n');
+            }
+        }
+    }
+    Ok(out)
+}
+
+#[derive(Serialize)]
+struct Versioned<'a, T> {
+    schema_version: u32,
+    #[serde(flatten)]
+    row: &'a T,
+}
+
+fn versioned<T>(rows: &[T]) -> Vec<Versioned<'_, T>> {
+    rows.iter()
+        .map(|row| Versioned {
+            schema_version: EXPORT_SCHEMA_VERSION,
+            row,
+        })
+        .collect()
 }
 
+fn export_files(data: &StatsData, format: ExportFormat, range: &DateRange) -> Result<Vec<ExportFile>, String> {
+    let extension = match format {
+        ExportFormat::Csv => "csv",
+        ExportFormat::Jsonl => "jsonl",
+    };
+    let file = |dataset: &str, rows: usize, content: String| ExportFile {
+        dataset: dataset.to_string(),
+        file_name: format!("focus-{}.{}", dataset, extension),
+        rows,
+        content,
+    };
+
+    let daily = daily_rows(data, range);
+    let daily_content = render_export(format, DAILY_COLUMNS, &daily, |r| {
+        vec![
+            r.schema_version.to_string(),
+            r.date.clone(),
+            r.focus_seconds.to_string(),
+            r.sessions_completed.to_string(),
+            r.sessions_abandoned.to_string(),
+            r.idle_seconds.to_string(),
+            r.blocks.to_string(),
+            r.websites_blocked.to_string(),
+            r.apps_blocked.to_string(),
+            r.app_seconds.to_string(),
+            r.distraction_seconds.to_string(),
+        ]
+    })?;
+
+    let sessions: Vec<FocusSession> = data
+        .sessions
+        .iter()
+        .filter(|s| range.contains(&s.started_at))
+        .cloned()
+        .collect();
+    let sessions_content = render_export(format, SESSION_COLUMNS, &versioned(&sessions), |r| {
+        let s = r.row;
+        vec![
+            r.schema_version.to_string(),
+            s.id.clone(),
+            s.started_at.clone(),
+            s.ended_at.clone(),
+            s.target_seconds.to_string(),
+            s.focus_seconds.to_string(),
+            s.idle_seconds.to_string(),
+            s.idle_periods.to_string(),
+            s.suspended_seconds.to_string(),
+            s.completed.to_string(),
+        ]
+    })?;
+
+    let blocks: Vec<BlockEvent> = data
+        .block_events
+        .iter()
+        .filter(|e| range.contains(&e.at))
+        .cloned()
+        .collect();
+    let blocks_content = render_export(format, BLOCK_COLUMNS, &versioned(&blocks), |r| {
+        let kind = match r.row.kind {
+            BlockKind::Website => "website",
+            BlockKind::App => "app",
+        };
+        vec![
+            r.schema_version.to_string(),
+            r.row.at.clone(),
+            kind.to_string(),
+            r.row.target.clone(),
+            r.row.session_id.clone().unwrap_or_default(),
+        ]
+    })?;
+
+    Ok(vec![
+        file("daily", daily.len(), daily_content),
+        file("sessions", sessions.len(), sessions_content),
+        file("blocks", blocks.len(), blocks_content),
+    ])
+}
+
+/// Exports daily aggregates, focus sessions and block events as one file each.
+/// Every row carries `schema_version`; see `EXPORT_SCHEMA_VERSION`.
 #[command]
-pub async fn get_block_count() -> Result<u64, String> {
-    Ok(load_stats().today.block_count)
+pub async fn export_stats(
+    format: ExportFormat,
+    range: Option<DateRange>,
+    output_dir: Option<String>,
+) -> Result<ExportResult, String> {
+    let range = range.unwrap_or_default();
+    range.validate()?;
+    let data = load_history(&range).map_err(|e| e.to_string())?;
+    let files = export_files(&data, format, &range)?;
+
+    let Some(dir) = output_dir else {
+        return Ok(ExportResult {
+            success: true,
+            message: format!("Exported {} rows", files.iter().map(|f| f.rows).sum::<usize>()),
+            schema_version: EXPORT_SCHEMA_VERSION,
+            files,
+            path: None,
+        });
+    };
+
+    let dir = PathBuf::from(dir);
+    for file in &files {
+        let path = dir.join(&file.file_name);
+        if let Err(e) = fs::write(&path, &file.content) {
+            return Ok(ExportResult {
+                success: false,
+                message: format!("Failed to write {}: {}", path.display(), e),
+                schema_version: EXPORT_SCHEMA_VERSION,
+                files,
+                path: None,
+            });
+        }
+    }
+
+    Ok(ExportResult {
+        success: true,
+        message: format!("Exported {} files to {}", files.len(), dir.display()),
+        schema_version: EXPORT_SCHEMA_VERSION,
+        files,
+        path: Some(dir.display().to_string()),
+    })
 }
 
 pub fn format_duration(seconds: u64) -> String {
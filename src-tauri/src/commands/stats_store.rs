@@ -0,0 +1,612 @@
+use crate::commands::stats::{
+    AppUsage, BlockEvent, BlockHeatmap, BlockKind, BlockedTarget, BudgetUsage, DailyStats, DateRange, FocusSession,
+    StatsData,
+};
+use chrono::{Duration, Local, NaiveDate};
+use once_cell::sync::Lazy;
+use rusqlite::{params, Connection, OptionalExtension, Row, Transaction};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum StoreError {
+    #[error("Stats database error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+    #[error("Stats database IO error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+// 所有读写共用一个连接，由互斥锁串行化；多步更新放在同一事务里
+static DB: Lazy<Mutex<Option<Connection>>> = Lazy::new(|| Mutex::new(None));
+
+const TOTAL_FOCUS: &str = "total_focus_seconds";
+const TOTAL_BLOCKS: &str = "total_blocks";
+const TOTAL_TEMPORARY_UNBLOCKS: &str = "total_temporary_unblocks";
+const TOTAL_SESSIONS_COMPLETED: &str = "total_sessions_completed";
+const LEGACY_IMPORTED: &str = "stats_json_imported";
+
+// 第 N 项把数据库从 user_version N 升级到 N + 1。已发布的迁移不能修改，只能追加
+const MIGRATIONS: &[&str] = &[r#"
+    CREATE TABLE daily_stats (
+        date TEXT PRIMARY KEY,
+        focus_seconds INTEGER NOT NULL DEFAULT 0,
+        block_count INTEGER NOT NULL DEFAULT 0,
+        websites_blocked INTEGER NOT NULL DEFAULT 0,
+        apps_blocked INTEGER NOT NULL DEFAULT 0,
+        temporary_unblocks INTEGER NOT NULL DEFAULT 0
+    );
+    CREATE TABLE counters (
+        name TEXT PRIMARY KEY,
+        value INTEGER NOT NULL DEFAULT 0
+    );
+    CREATE TABLE sessions (
+        id TEXT PRIMARY KEY,
+        started_at TEXT NOT NULL,
+        ended_at TEXT NOT NULL,
+        target_seconds INTEGER NOT NULL,
+        focus_seconds INTEGER NOT NULL,
+        idle_seconds INTEGER NOT NULL,
+        idle_periods INTEGER NOT NULL,
+        suspended_seconds INTEGER NOT NULL,
+        completed INTEGER NOT NULL
+    );
+    CREATE INDEX sessions_started_at ON sessions (started_at);
+    CREATE TABLE block_events (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        at TEXT NOT NULL,
+        kind TEXT NOT NULL,
+        target TEXT NOT NULL DEFAULT '',
+        session_id TEXT
+    );
+    CREATE INDEX block_events_at ON block_events (at);
+    CREATE INDEX block_events_target ON block_events (target, at);
+    CREATE TABLE app_usage (
+        date TEXT NOT NULL,
+        app TEXT NOT NULL,
+        name TEXT NOT NULL,
+        exe TEXT,
+        seconds INTEGER NOT NULL DEFAULT 0,
+        distracting INTEGER NOT NULL DEFAULT 0,
+        PRIMARY KEY (date, app)
+    );
+    CREATE TABLE budget_usage (
+        day TEXT NOT NULL,
+        budget_id TEXT NOT NULL,
+        seconds INTEGER NOT NULL DEFAULT 0,
+        PRIMARY KEY (day, budget_id)
+    );
+    CREATE TABLE meta (
+        key TEXT PRIMARY KEY,
+        value TEXT NOT NULL
+    );
+"#];
+
+fn get_data_path(file: &str) -> PathBuf {
+    let path = dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("FocusKeeper")
+        .join(file);
+
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    path
+}
+
+fn open() -> Result<Connection, StoreError> {
+    let mut conn = Connection::open(get_data_path("stats.db"))?;
+    conn.busy_timeout(std::time::Duration::from_secs(5))?;
+    conn.query_row("PRAGMA journal_mode = WAL", [], |_| Ok(()))?;
+    migrate(&mut conn)?;
+    import_legacy(&mut conn)?;
+    Ok(conn)
+}
+
+fn migrate(conn: &mut Connection) -> Result<(), StoreError> {
+    let version: i64 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    for (index, sql) in MIGRATIONS.iter().enumerate().skip(version.max(0) as usize) {
+        let tx = conn.transaction()?;
+        tx.execute_batch(sql)?;
+        tx.pragma_update(None, "user_version", index as i64 + 1)?;
+        tx.commit()?;
+        log::info!("stats database migrated to version {}", index + 1);
+    }
+    Ok(())
+}
+
+/// Runs `f` on the shared connection, opening and migrating the database on first use.
+pub fn with_db<T>(f: impl FnOnce(&mut Connection) -> Result<T, StoreError>) -> Result<T, StoreError> {
+    let mut guard = DB.lock().unwrap_or_else(|e| e.into_inner());
+    if guard.is_none() {
+        *guard = Some(open()?);
+    }
+    match guard.as_mut() {
+        Some(conn) => f(conn),
+        None => unreachable!("connection opened above"),
+    }
+}
+
+fn in_transaction<T>(f: impl FnOnce(&Transaction) -> Result<T, StoreError>) -> Result<T, StoreError> {
+    with_db(|conn| {
+        let tx = conn.transaction()?;
+        let value = f(&tx)?;
+        tx.commit()?;
+        Ok(value)
+    })
+}
+
+// 旧版本把所有统计写在 stats.json 里，第一次打开数据库时导入一次
+fn import_legacy(conn: &mut Connection) -> Result<(), StoreError> {
+    let path = get_data_path("stats.json");
+    if !path.exists() {
+        return Ok(());
+    }
+    let imported: Option<String> = conn
+        .query_row("SELECT value FROM meta WHERE key = ?1", [LEGACY_IMPORTED], |row| row.get(0))
+        .optional()?;
+    if imported.is_some() {
+        return Ok(());
+    }
+
+    let data = match fs::read_to_string(&path).map(|content| serde_json::from_str::<StatsData>(&content)) {
+        Ok(Ok(data)) => data,
+        Ok(Err(e)) => {
+            log::error!("stats.json is not valid, skipping import: {}", e);
+            return skip_legacy(conn, &path);
+        }
+        Err(e) => {
+            log::error!("failed to read stats.json, skipping import: {}", e);
+            return skip_legacy(conn, &path);
+        }
+    };
+
+    let tx = conn.transaction()?;
+    import_stats(&tx, &data)?;
+    tx.execute(
+        "INSERT INTO meta (key, value) VALUES (?1, ?2)",
+        params![LEGACY_IMPORTED, Local::now().to_rfc3339()],
+    )?;
+    tx.commit()?;
+
+    // 原文件改名保留，方便出问题时找回
+    if let Err(e) = fs::rename(&path, path.with_extension("json.imported")) {
+        log::warn!("failed to rename imported stats.json: {}", e);
+    }
+    log::info!("imported stats.json into the stats database");
+    Ok(())
+}
+
+// 无法导入的文件也记下已处理并移到一边，避免每次启动都重复尝试和报错
+fn skip_legacy(conn: &Connection, path: &Path) -> Result<(), StoreError> {
+    conn.execute(
+        "INSERT INTO meta (key, value) VALUES (?1, ?2)",
+        params![LEGACY_IMPORTED, Local::now().to_rfc3339()],
+    )?;
+    if let Err(e) = fs::rename(path, path.with_extension("json.invalid")) {
+        log::warn!("failed to rename invalid stats.json: {}", e);
+    }
+    Ok(())
+}
+
+fn import_stats(tx: &Transaction, data: &StatsData) -> Result<(), StoreError> {
+    let mut daily_focus = data.daily_focus.clone();
+    // 更早的版本没有按日记录，只有当日统计
+    daily_focus
+        .entry(data.today.date.clone())
+        .or_insert(data.today.focus_duration_seconds);
+    for (date, seconds) in &daily_focus {
+        bump_daily(tx, date, "focus_seconds", *seconds)?;
+    }
+    bump_daily(tx, &data.today.date, "block_count", data.today.block_count)?;
+    bump_daily(tx, &data.today.date, "websites_blocked", data.today.websites_blocked)?;
+    bump_daily(tx, &data.today.date, "apps_blocked", data.today.apps_blocked)?;
+    bump_daily(tx, &data.today.date, "temporary_unblocks", data.today.temporary_unblocks)?;
+
+    bump(tx, TOTAL_FOCUS, data.total_focus_seconds)?;
+    bump(tx, TOTAL_BLOCKS, data.total_blocks)?;
+    bump(tx, TOTAL_TEMPORARY_UNBLOCKS, data.total_temporary_unblocks)?;
+    bump(tx, TOTAL_SESSIONS_COMPLETED, data.total_sessions_completed)?;
+
+    for session in &data.sessions {
+        insert_session(tx, session)?;
+    }
+    for event in &data.block_events {
+        insert_block_event(tx, event)?;
+    }
+    for (date, apps) in &data.app_usage {
+        for (key, usage) in apps {
+            upsert_app_usage(tx, date, key, usage)?;
+        }
+    }
+    for (id, seconds) in &data.budget_usage.seconds {
+        tx.execute(
+            "INSERT OR REPLACE INTO budget_usage (day, budget_id, seconds) VALUES (?1, ?2, ?3)",
+            params![data.budget_usage.day, id, *seconds as i64],
+        )?;
+    }
+    Ok(())
+}
+
+fn bump(tx: &Transaction, name: &str, by: u64) -> Result<(), StoreError> {
+    tx.execute(
+        "INSERT INTO counters (name, value) VALUES (?1, ?2)
+         ON CONFLICT (name) DO UPDATE SET value = value + excluded.value",
+        params![name, by as i64],
+    )?;
+    Ok(())
+}
+
+// column 只来自本文件里的常量，不会拼入外部输入
+fn bump_daily(tx: &Transaction, date: &str, column: &'static str, by: u64) -> Result<(), StoreError> {
+    tx.execute(
+        &format!(
+            "INSERT INTO daily_stats (date, {column}) VALUES (?1, ?2)
+             ON CONFLICT (date) DO UPDATE SET {column} = {column} + excluded.{column}"
+        ),
+        params![date, by as i64],
+    )?;
+    Ok(())
+}
+
+fn insert_session(tx: &Transaction, session: &FocusSession) -> Result<(), StoreError> {
+    // 旧记录没有 id，用开始时间代替
+    let id = if session.id.is_empty() { &session.started_at } else { &session.id };
+    tx.execute(
+        "INSERT OR REPLACE INTO sessions (id, started_at, ended_at, target_seconds, focus_seconds,
+             idle_seconds, idle_periods, suspended_seconds, completed)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        params![
+            id,
+            session.started_at,
+            session.ended_at,
+            session.target_seconds as i64,
+            session.focus_seconds as i64,
+            session.idle_seconds as i64,
+            session.idle_periods,
+            session.suspended_seconds as i64,
+            session.completed,
+        ],
+    )?;
+    Ok(())
+}
+
+fn kind_name(kind: BlockKind) -> &'static str {
+    match kind {
+        BlockKind::Website => "website",
+        BlockKind::App => "app",
+    }
+}
+
+fn parse_kind(kind: &str) -> BlockKind {
+    if kind == "website" {
+        BlockKind::Website
+    } else {
+        BlockKind::App
+    }
+}
+
+fn insert_block_event(tx: &Transaction, event: &BlockEvent) -> Result<(), StoreError> {
+    tx.execute(
+        "INSERT INTO block_events (at, kind, target, session_id) VALUES (?1, ?2, ?3, ?4)",
+        params![event.at, kind_name(event.kind), event.target, event.session_id],
+    )?;
+    Ok(())
+}
+
+fn upsert_app_usage(tx: &Transaction, date: &str, key: &str, usage: &AppUsage) -> Result<(), StoreError> {
+    tx.execute(
+        "INSERT INTO app_usage (date, app, name, exe, seconds, distracting) VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+         ON CONFLICT (date, app) DO UPDATE SET
+             seconds = seconds + excluded.seconds,
+             distracting = distracting OR excluded.distracting,
+             exe = COALESCE(excluded.exe, exe)",
+        params![date, key, usage.name, usage.exe, usage.seconds as i64, usage.distracting],
+    )?;
+    Ok(())
+}
+
+fn prune(tx: &Transaction, oldest: &str) -> Result<(), StoreError> {
+    tx.execute("DELETE FROM block_events WHERE at < ?1", [oldest])?;
+    tx.execute("DELETE FROM sessions WHERE started_at < ?1", [oldest])?;
+    tx.execute("DELETE FROM app_usage WHERE date < ?1", [oldest])?;
+    Ok(())
+}
+
+// 时间戳以本地日期开头，按字符串比较就能走索引：[from, to 的次日)
+fn timestamp_bounds(range: &DateRange) -> (String, String) {
+    let from = range.from.clone().unwrap_or_default();
+    let to = range
+        .to
+        .as_deref()
+        .and_then(|to| NaiveDate::parse_from_str(to, "%Y-%m-%d").ok())
+        .map(|to| (to + Duration::days(1)).format("%Y-%m-%d").to_string())
+        .unwrap_or_else(|| "9999".to_string());
+    (from, to)
+}
+
+fn date_bounds(range: &DateRange) -> (String, String) {
+    (
+        range.from.clone().unwrap_or_default(),
+        range.to.clone().unwrap_or_else(|| "9999".to_string()),
+    )
+}
+
+fn counter(conn: &Connection, name: &str) -> Result<u64, StoreError> {
+    let value: Option<i64> = conn
+        .query_row("SELECT value FROM counters WHERE name = ?1", [name], |row| row.get(0))
+        .optional()?;
+    Ok(value.unwrap_or(0) as u64)
+}
+
+fn daily_stats(conn: &Connection, date: &str) -> Result<DailyStats, StoreError> {
+    let stats = conn
+        .query_row(
+            "SELECT focus_seconds, block_count, websites_blocked, apps_blocked, temporary_unblocks
+             FROM daily_stats WHERE date = ?1",
+            [date],
+            |row| {
+                Ok(DailyStats {
+                    date: date.to_string(),
+                    focus_duration_seconds: row.get::<_, i64>(0)? as u64,
+                    block_count: row.get::<_, i64>(1)? as u64,
+                    websites_blocked: row.get::<_, i64>(2)? as u64,
+                    apps_blocked: row.get::<_, i64>(3)? as u64,
+                    temporary_unblocks: row.get::<_, i64>(4)? as u64,
+                })
+            },
+        )
+        .optional()?;
+    Ok(stats.unwrap_or_default())
+}
+
+/// Today's numbers, running totals, per-day focus and the current budget usage.
+/// History lists are left empty; see `load_history`.
+pub fn load_summary() -> Result<StatsData, StoreError> {
+    with_db(|conn| {
+        let today = Local::now().format("%Y-%m-%d").to_string();
+        let mut daily_focus = BTreeMap::new();
+        let mut stmt = conn.prepare("SELECT date, focus_seconds FROM daily_stats WHERE focus_seconds > 0")?;
+        for row in stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)? as u64)))? {
+            let (date, seconds) = row?;
+            daily_focus.insert(date, seconds);
+        }
+
+        let day: Option<String> = conn
+            .query_row("SELECT MAX(day) FROM budget_usage", [], |row| row.get(0))
+            .optional()?
+            .flatten();
+        let budget_usage = match day {
+            Some(day) => BudgetUsage {
+                seconds: budget_seconds(conn, &day)?,
+                day,
+            },
+            None => BudgetUsage::default(),
+        };
+
+        Ok(StatsData {
+            today: daily_stats(conn, &today)?,
+            total_focus_seconds: counter(conn, TOTAL_FOCUS)?,
+            total_blocks: counter(conn, TOTAL_BLOCKS)?,
+            total_temporary_unblocks: counter(conn, TOTAL_TEMPORARY_UNBLOCKS)?,
+            total_sessions_completed: counter(conn, TOTAL_SESSIONS_COMPLETED)?,
+            budget_usage,
+            daily_focus,
+            ..Default::default()
+        })
+    })
+}
+
+pub fn add_focus_seconds(date: &str, seconds: u64) -> Result<(), StoreError> {
+    in_transaction(|tx| {
+        bump_daily(tx, date, "focus_seconds", seconds)?;
+        bump(tx, TOTAL_FOCUS, seconds)
+    })
+}
+
+pub fn record_block(event: &BlockEvent, oldest: &str) -> Result<(), StoreError> {
+    let date = event.at.get(..10).unwrap_or_default();
+    in_transaction(|tx| {
+        bump_daily(tx, date, "block_count", 1)?;
+        match event.kind {
+            BlockKind::Website => bump_daily(tx, date, "websites_blocked", 1)?,
+            BlockKind::App => bump_daily(tx, date, "apps_blocked", 1)?,
+        }
+        bump(tx, TOTAL_BLOCKS, 1)?;
+        insert_block_event(tx, event)?;
+        prune(tx, oldest)
+    })
+}
+
+pub fn record_temporary_unblock(date: &str) -> Result<(), StoreError> {
+    in_transaction(|tx| {
+        bump_daily(tx, date, "temporary_unblocks", 1)?;
+        bump(tx, TOTAL_TEMPORARY_UNBLOCKS, 1)
+    })
+}
+
+pub fn record_session(session: &FocusSession, oldest: &str) -> Result<(), StoreError> {
+    in_transaction(|tx| {
+        insert_session(tx, session)?;
+        if session.completed {
+            bump(tx, TOTAL_SESSIONS_COMPLETED, 1)?;
+        }
+        prune(tx, oldest)
+    })
+}
+
+pub fn add_app_usage(date: &str, usage: &[AppUsage], oldest: &str) -> Result<(), StoreError> {
+    in_transaction(|tx| {
+        for sample in usage {
+            upsert_app_usage(tx, date, &sample.name.to_lowercase(), sample)?;
+        }
+        prune(tx, oldest)
+    })
+}
+
+fn budget_seconds(conn: &Connection, day: &str) -> Result<BTreeMap<String, u64>, StoreError> {
+    let mut stmt = conn.prepare("SELECT budget_id, seconds FROM budget_usage WHERE day = ?1")?;
+    let rows = stmt.query_map([day], |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)? as u64)))?;
+    Ok(rows.collect::<Result<_, _>>()?)
+}
+
+pub fn budget_usage(day: &str) -> Result<BTreeMap<String, u64>, StoreError> {
+    with_db(|conn| budget_seconds(conn, day))
+}
+
+/// Adds `seconds` to each budget in `ids` for budget day `day`; earlier days are dropped.
+pub fn add_budget_usage(day: &str, ids: &[String], seconds: u64) -> Result<BTreeMap<String, u64>, StoreError> {
+    in_transaction(|tx| {
+        tx.execute("DELETE FROM budget_usage WHERE day <> ?1", [day])?;
+        for id in ids {
+            tx.execute(
+                "INSERT INTO budget_usage (day, budget_id, seconds) VALUES (?1, ?2, ?3)
+                 ON CONFLICT (day, budget_id) DO UPDATE SET seconds = seconds + excluded.seconds",
+                params![day, id, seconds as i64],
+            )?;
+        }
+        budget_seconds(tx, day)
+    })
+}
+
+pub fn app_usage(range: &DateRange) -> Result<BTreeMap<String, BTreeMap<String, AppUsage>>, StoreError> {
+    let (from, to) = date_bounds(range);
+    with_db(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT date, app, name, exe, seconds, distracting FROM app_usage
+             WHERE date >= ?1 AND date <= ?2",
+        )?;
+        let rows = stmt.query_map([from, to], |row| {
+            let usage = AppUsage {
+                name: row.get(2)?,
+                exe: row.get(3)?,
+                seconds: row.get::<_, i64>(4)? as u64,
+                distracting: row.get(5)?,
+            };
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, usage))
+        })?;
+        let mut usage: BTreeMap<String, BTreeMap<String, AppUsage>> = BTreeMap::new();
+        for row in rows {
+            let (date, app, entry) = row?;
+            usage.entry(date).or_default().insert(app, entry);
+        }
+        Ok(usage)
+    })
+}
+
+fn session_from_row(row: &Row) -> rusqlite::Result<FocusSession> {
+    Ok(FocusSession {
+        id: row.get(0)?,
+        started_at: row.get(1)?,
+        ended_at: row.get(2)?,
+        target_seconds: row.get::<_, i64>(3)? as u64,
+        focus_seconds: row.get::<_, i64>(4)? as u64,
+        idle_seconds: row.get::<_, i64>(5)? as u64,
+        idle_periods: row.get(6)?,
+        suspended_seconds: row.get::<_, i64>(7)? as u64,
+        completed: row.get(8)?,
+    })
+}
+
+/// Sessions started within `range`, oldest first.
+pub fn sessions(range: &DateRange) -> Result<Vec<FocusSession>, StoreError> {
+    let (from, to) = timestamp_bounds(range);
+    with_db(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT id, started_at, ended_at, target_seconds, focus_seconds, idle_seconds,
+                 idle_periods, suspended_seconds, completed
+             FROM sessions WHERE started_at >= ?1 AND started_at < ?2 ORDER BY started_at",
+        )?;
+        let rows = stmt.query_map([from, to], session_from_row)?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    })
+}
+
+/// Block events within `range`, oldest first.
+pub fn block_events(range: &DateRange) -> Result<Vec<BlockEvent>, StoreError> {
+    let (from, to) = timestamp_bounds(range);
+    with_db(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT at, kind, target, session_id FROM block_events
+             WHERE at >= ?1 AND at < ?2 ORDER BY at, id",
+        )?;
+        let rows = stmt.query_map([from, to], |row| {
+            Ok(BlockEvent {
+                at: row.get(0)?,
+                kind: parse_kind(&row.get::<_, String>(1)?),
+                target: row.get(2)?,
+                session_id: row.get(3)?,
+            })
+        })?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    })
+}
+
+pub fn top_blocked(range: &DateRange, n: usize) -> Result<Vec<BlockedTarget>, StoreError> {
+    let (from, to) = timestamp_bounds(range);
+    with_db(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT target, kind, COUNT(*) AS count, MAX(at) AS last_at FROM block_events
+             WHERE at >= ?1 AND at < ?2 AND target <> ''
+             GROUP BY target, kind
+             ORDER BY count DESC, last_at DESC
+             LIMIT ?3",
+        )?;
+        let rows = stmt.query_map(params![from, to, n as i64], |row| {
+            Ok(BlockedTarget {
+                target: row.get(0)?,
+                kind: parse_kind(&row.get::<_, String>(1)?),
+                count: row.get::<_, i64>(2)? as u64,
+                last_at: row.get(3)?,
+            })
+        })?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    })
+}
+
+pub fn block_heatmap(range: &DateRange) -> Result<BlockHeatmap, StoreError> {
+    let (from, to) = timestamp_bounds(range);
+    with_db(|conn| {
+        // 按记录时的本地时间统计；strftime('%w') 以周日为 0，换成周一为 0
+        let mut stmt = conn.prepare(
+            "SELECT (CAST(strftime('%w', substr(at, 1, 10)) AS INTEGER) + 6) % 7 AS weekday,
+                 CAST(substr(at, 12, 2) AS INTEGER) AS hour,
+                 COUNT(*)
+             FROM block_events
+             WHERE at >= ?1 AND at < ?2
+             GROUP BY weekday, hour",
+        )?;
+        let rows = stmt.query_map([from, to], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?, row.get::<_, i64>(2)? as u64))
+        })?;
+        let mut cells = vec![vec![0u64; 24]; 7];
+        for row in rows {
+            let (weekday, hour, count) = row?;
+            if let Some(cell) = cells.get_mut(weekday as usize).and_then(|day| day.get_mut(hour as usize)) {
+                *cell += count;
+            }
+        }
+        Ok(BlockHeatmap {
+            max: cells.iter().flatten().copied().max().unwrap_or(0),
+            total: cells.iter().flatten().sum(),
+            cells,
+        })
+    })
+}
+
+/// Deletes all recorded stats; the schema, import marker and budget usage are kept
+/// (budget usage is enforcement state, not a statistic).
+pub fn clear() -> Result<(), StoreError> {
+    in_transaction(|tx| {
+        tx.execute_batch(
+            "DELETE FROM daily_stats;
+             DELETE FROM counters;
+             DELETE FROM sessions;
+             DELETE FROM block_events;
+             DELETE FROM app_usage;",
+        )?;
+        Ok(())
+    })
+}
@@ -0,0 +1,233 @@
+//! End-to-end tests of the blocking commands against the in-memory blockers.
+
+use super::config::{export_config, set_blocked_apps, ConfigBundle};
+use super::hosts::{
+    add_website, disable_category, enable_category, get_blocked_websites, import_blocklist, preview_blocklist_import,
+    remove_website, unblock_all,
+};
+use super::lock::{lock_focus, set_password, unlock};
+use super::process::{
+    find_processes_cmd, is_app_running_cmd, kill_app_processes, kill_process_cmd, set_process_allowlist,
+    update_process_watch,
+};
+use crate::blocker::fake::{MemoryAppBlocker, MemoryWebsiteBlocker};
+use crate::blocker::process::{ProcessInfo, ProcessMatcher, TerminateOptions};
+use crate::blocker::watch::WatchOptions;
+use crate::blocker::state::BlockerState;
+use std::sync::{Arc, Mutex, MutexGuard, Once};
+use tauri::async_runtime::block_on;
+use tauri::test::{mock_builder, mock_context, noop_assets, MockRuntime};
+use tauri::{App, Manager};
+
+// 命令会读写锁定状态、统计等数据文件：指向临时目录，并串行执行避免互相干扰
+fn setup() -> MutexGuard<'static, ()> {
+    static SERIAL: Mutex<()> = Mutex::new(());
+    static DATA_DIR: Once = Once::new();
+    DATA_DIR.call_once(|| {
+        let dir = std::env::temp_dir().join(format!("focus-keeper-tests-{}", std::process::id()));
+        std::env::set_var("XDG_DATA_HOME", &dir);
+        std::env::set_var("HOME", &dir);
+    });
+    SERIAL.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn process(pid: u32, name: &str) -> ProcessInfo {
+    ProcessInfo {
+        name: name.to_string(),
+        pid,
+        exe: None,
+        parent_pid: None,
+        cmd: Vec::new(),
+        start_time: 0,
+        user: None,
+        cpu_usage: 0.0,
+        memory_bytes: 0,
+    }
+}
+
+fn mock_app(processes: Vec<ProcessInfo>) -> (App<MockRuntime>, Arc<MemoryAppBlocker>) {
+    let apps = Arc::new(MemoryAppBlocker::new(processes));
+    let state = BlockerState::new(Arc::new(MemoryWebsiteBlocker::new()), apps.clone());
+    let app = mock_builder()
+        .manage(state)
+        .build(mock_context(noop_assets()))
+        .expect("failed to build mock app");
+    (app, apps)
+}
+
+fn blocked(app: &App<MockRuntime>) -> Vec<String> {
+    block_on(get_blocked_websites(app.state())).unwrap().data.unwrap_or_default()
+}
+
+#[test]
+fn add_and_remove_website_normalize_rules() {
+    let _guard = setup();
+    let (app, _) = mock_app(Vec::new());
+
+    assert!(block_on(add_website(app.state(), " YouTube.com. ".to_string())).unwrap().success);
+    assert!(block_on(add_website(app.state(), "*.reddit.com".to_string())).unwrap().success);
+    assert_eq!(blocked(&app), vec!["youtube.com", "*.reddit.com"]);
+
+    // 删除时输入的写法与保存的规则不同也应能匹配
+    assert!(block_on(remove_website(app.state(), "YOUTUBE.COM".to_string())).unwrap().success);
+    assert_eq!(blocked(&app), vec!["*.reddit.com"]);
+
+    assert!(block_on(unblock_all(app.state())).unwrap().success);
+    assert!(blocked(&app).is_empty());
+}
+
+#[test]
+fn add_website_rejects_invalid_rule() {
+    let _guard = setup();
+    let (app, _) = mock_app(Vec::new());
+
+    let result = block_on(add_website(app.state(), "not a domain".to_string())).unwrap();
+    assert!(!result.success);
+    assert!(blocked(&app).is_empty());
+}
+
+#[test]
+fn import_blocklist_skips_existing_rules() {
+    let _guard = setup();
+    let (app, _) = mock_app(Vec::new());
+    block_on(add_website(app.state(), "example.com".to_string())).unwrap();
+
+    let content = "0.0.0.0 example.com\n0.0.0.0 news.example.org\n".to_string();
+    let preview = block_on(preview_blocklist_import(app.state(), content.clone(), None)).unwrap();
+    assert_eq!(preview.rules(), vec!["news.example.org"]);
+    assert_eq!(preview.duplicates.len(), 1);
+
+    let result = block_on(import_blocklist(app.state(), content, None)).unwrap();
+    assert!(result.success);
+    assert_eq!(blocked(&app), vec!["example.com", "news.example.org"]);
+}
+
+#[test]
+fn locked_mode_refuses_to_remove_websites() {
+    let _guard = setup();
+    let (app, _) = mock_app(Vec::new());
+    block_on(add_website(app.state(), "example.com".to_string())).unwrap();
+
+    assert!(block_on(set_password("1234".to_string())).unwrap().success);
+    assert!(block_on(lock_focus(5)).unwrap().success);
+    let removed = block_on(remove_website(app.state(), "example.com".to_string())).unwrap();
+    let cleared = block_on(unblock_all(app.state())).unwrap();
+    assert!(block_on(unlock("1234".to_string())).unwrap().success);
+
+    assert!(!removed.success);
+    assert!(!cleared.success);
+    assert_eq!(blocked(&app), vec!["example.com"]);
+}
+
+#[test]
+fn locked_mode_refuses_to_extend_process_allowlist() {
+    let _guard = setup();
+    block_on(set_process_allowlist(vec!["editor".to_string()])).unwrap();
+
+    assert!(block_on(set_password("1234".to_string())).unwrap().success);
+    assert!(block_on(lock_focus(5)).unwrap().success);
+    let extended = block_on(set_process_allowlist(vec!["editor".to_string(), "Game.exe".to_string()]));
+    let shrunk = block_on(set_process_allowlist(Vec::new()));
+    assert!(block_on(unlock("1234".to_string())).unwrap().success);
+
+    assert!(extended.is_err());
+    assert_eq!(shrunk.unwrap(), Vec::<String>::new());
+}
+
+#[test]
+fn locked_mode_refuses_to_loosen_process_watch() {
+    let _guard = setup();
+    let watch = |patterns: &[&str]| WatchOptions {
+        blocked: patterns.iter().copied().map(ProcessMatcher::exact).collect(),
+        ..Default::default()
+    };
+    assert!(block_on(update_process_watch(watch(&["game"]))).is_ok());
+
+    assert!(block_on(set_password("1234".to_string())).unwrap().success);
+    assert!(block_on(lock_focus(5)).unwrap().success);
+    let removed = block_on(update_process_watch(watch(&[])));
+    let added = block_on(update_process_watch(watch(&["game", "chat"])));
+    assert!(block_on(unlock("1234".to_string())).unwrap().success);
+    block_on(update_process_watch(WatchOptions::default())).unwrap();
+
+    assert!(removed.is_err());
+    assert!(added.is_ok());
+}
+
+#[test]
+fn locked_mode_refuses_to_clear_blocked_apps() {
+    let _guard = setup();
+    block_on(set_blocked_apps(vec!["game".to_string()])).unwrap();
+
+    assert!(block_on(set_password("1234".to_string())).unwrap().success);
+    assert!(block_on(lock_focus(5)).unwrap().success);
+    let cleared = block_on(set_blocked_apps(Vec::new()));
+    let added = block_on(set_blocked_apps(vec!["game".to_string(), "chat".to_string()]));
+    assert!(block_on(unlock("1234".to_string())).unwrap().success);
+    block_on(set_blocked_apps(Vec::new())).unwrap();
+
+    assert!(cleared.is_err());
+    assert!(added.is_ok());
+}
+
+#[test]
+fn export_config_keeps_category_rules_out_of_custom_rules() {
+    let _guard = setup();
+    let (app, _) = mock_app(Vec::new());
+    block_on(add_website(app.state(), "example.com".to_string())).unwrap();
+    assert!(block_on(enable_category(app.state(), "social".to_string())).unwrap().success);
+    assert!(blocked(&app).contains(&"reddit.com/**".to_string()));
+
+    let exported = block_on(export_config(app.state(), None)).unwrap();
+    block_on(disable_category(app.state(), "social".to_string())).unwrap();
+
+    let bundle: ConfigBundle = serde_json::from_str(&exported.bundle.unwrap()).unwrap();
+    assert_eq!(bundle.websites.rules, vec!["example.com"]);
+    assert_eq!(bundle.websites.categories, vec!["social"]);
+    assert_eq!(blocked(&app), vec!["example.com"]);
+}
+
+#[test]
+fn kill_process_cmd_kills_only_the_requested_pid() {
+    let _guard = setup();
+    let child = |pid, parent| ProcessInfo {
+        parent_pid: Some(parent),
+        ..process(pid, "game-helper")
+    };
+    let (app, apps) = mock_app(vec![process(10, "game"), child(12, 10), process(11, "game"), child(13, 11)]);
+
+    let result = block_on(kill_process_cmd(app.handle().clone(), app.state(), 10, None)).unwrap();
+    assert!(result.success);
+    assert_eq!(result.killed_count, Some(1));
+    assert_eq!(apps.killed(), vec![10]);
+
+    let tree = TerminateOptions {
+        kill_tree: true,
+        ..Default::default()
+    };
+    let result = block_on(kill_process_cmd(app.handle().clone(), app.state(), 11, Some(tree))).unwrap();
+    assert_eq!(result.killed_count, Some(2));
+    assert_eq!(apps.killed(), vec![10, 11, 13]);
+
+    let missing = block_on(kill_process_cmd(app.handle().clone(), app.state(), 42, None)).unwrap();
+    assert!(!missing.success);
+}
+
+#[test]
+fn kill_app_processes_kills_every_instance() {
+    let _guard = setup();
+    let (app, apps) = mock_app(vec![process(10, "game.exe"), process(11, "game"), process(12, "editor")]);
+
+    let running = block_on(is_app_running_cmd(app.state(), "game".to_string(), None)).unwrap();
+    assert_eq!(running.message, "game is running");
+
+    let result = block_on(kill_app_processes(app.handle().clone(), app.state(), "game".to_string(), None, None)).unwrap();
+    assert!(result.success);
+    assert_eq!(result.killed_count, Some(2));
+    assert_eq!(apps.killed(), vec![10, 11]);
+
+    let running = block_on(is_app_running_cmd(app.state(), "game".to_string(), None)).unwrap();
+    assert_eq!(running.message, "game is not running");
+    let remaining = block_on(find_processes_cmd(app.state(), "editor".to_string(), None)).unwrap();
+    assert_eq!(remaining.processes.map(|p| p.len()), Some(1));
+}
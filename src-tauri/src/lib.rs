@@ -1,14 +1,17 @@
-mod blocker;
-mod commands;
+pub mod blocker;
+pub mod commands;
 
-use commands::{hosts, process, stats, focus, lock};
+use blocker::state::BlockerState;
+use commands::{activity, budget, config, goals, hosts, process, report, stats, focus, lock};
 use tauri::menu::{Menu, MenuItem};
+use tauri::Manager;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_fs::init())
+        .manage(BlockerState::system())
         .setup(|app| {
             #[cfg(desktop)]
             {
@@ -17,7 +20,7 @@ pub fn run() {
                 let quit_item = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
                 
                 let menu = Menu::with_items(app, &[&hide_item, &show_item, &quit_item])?;
-                app.set_menu(Some(menu))?;
+                app.set_menu(menu)?;
             }
 
             if cfg!(debug_assertions) {
@@ -30,19 +33,78 @@ pub fn run() {
 
             app.handle().plugin(tauri_plugin_log::Builder::default().build())?;
 
+            blocker::dns::set_block_observer(hosts::record_dns_block);
+            // 上次运行若在允许名单模式下异常退出，恢复系统 DNS，避免断网
+            if let Err(e) = blocker::dns::recover_system_dns() {
+                log::error!("failed to restore system DNS: {}", e);
+            }
+            // DNS 后端有持久化的屏蔽规则时重新启动本地解析器
+            if let Err(e) = blocker::dns::sync_resolver() {
+                log::error!("failed to start local resolver: {}", e);
+            }
+            // 内置分类列表升级后，把新版本应用到已启用的分类
+            let blockers = app.state::<BlockerState>();
+            if let Err(e) = blocker::categories::sync_categories(blockers.website().as_ref()) {
+                log::error!("failed to update block categories: {}", e);
+            }
+            hosts::resume_pending_reblocks(app.handle());
+            budget::start_budget_tracking(app.handle().clone());
+            activity::start_activity_tracking(app.handle().clone());
+            focus::start_idle_monitor(app.handle().clone());
+
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             hosts::get_blocked_websites,
+            hosts::get_blocked_entries,
+            hosts::refresh_website_rules,
             hosts::add_website,
             hosts::remove_website,
             hosts::block_websites,
             hosts::unblock_all,
+            hosts::enable_allow_only_mode,
+            hosts::disable_allow_only_mode,
+            hosts::get_allow_only_state,
+            hosts::get_blocking_backend,
+            hosts::set_blocking_backend,
+            hosts::get_dns_config,
+            hosts::set_dns_config,
+            hosts::get_block_categories,
+            hosts::enable_category,
+            hosts::disable_category,
+            hosts::preview_blocklist_import,
+            hosts::import_blocklist,
+            hosts::temporarily_unblock,
+            hosts::cancel_temporary_unblock,
+            hosts::get_temporary_unblocks,
+            budget::get_budgets,
+            budget::set_budgets,
+            budget::get_budget_status,
+            budget::report_site_usage,
+            activity::get_active_window,
+            activity::set_activity_tracking,
+            stats::get_app_usage,
+            stats::get_app_usage_history,
+            stats::get_focus_sessions,
+            stats::export_stats,
+            stats::top_blocked,
+            stats::get_block_heatmap,
+            focus::get_idle_options,
+            focus::set_idle_options,
             process::check_processes,
             process::kill_process_cmd,
             process::kill_app_processes,
             process::is_app_running_cmd,
             process::find_processes_cmd,
+            process::get_app_catalog,
+            process::check_apps,
+            process::kill_apps,
+            process::get_process_allowlist,
+            process::set_process_allowlist,
+            process::start_process_watch,
+            process::update_process_watch,
+            process::stop_process_watch,
+            process::get_process_watch_status,
             stats::get_stats,
             stats::add_focus_time,
             stats::add_block,
@@ -54,6 +116,14 @@ pub fn run() {
             focus::resume_focus,
             focus::stop_focus,
             focus::get_focus_state,
+            focus::run_focus_timer,
+            focus::get_timer_options,
+            focus::set_timer_options,
+            goals::get_goals,
+            goals::set_goals,
+            goals::get_goal_progress,
+            goals::get_achievements,
+            report::generate_report,
             lock::has_password,
             lock::set_password,
             lock::verify_password,
@@ -61,6 +131,11 @@ pub fn run() {
             lock::lock_focus,
             lock::unlock,
             lock::get_lock_state,
+            config::get_user_config,
+            config::set_user_config,
+            config::set_blocked_apps,
+            config::export_config,
+            config::import_config,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
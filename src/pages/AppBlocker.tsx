@@ -11,6 +11,12 @@ interface ProcessInfo {
   name: string
   pid: number
   exe?: string
+  parent_pid?: number
+  cmd: string[]
+  start_time: number
+  user?: string
+  cpu_usage: number
+  memory_bytes: number
 }
 
 interface ProcessResult {
@@ -28,16 +34,30 @@ export function AppBlocker() {
   const [monitoring, setMonitoring] = useState(false)
   const [loading, setLoading] = useState(false)
 
+  const [loaded, setLoaded] = useState(false)
+
   useEffect(() => {
-    const saved = localStorage.getItem('blockedApps')
-    if (saved) {
-      setBlockedApps(JSON.parse(saved))
-    }
+    // 应用列表保存在后端，以便随配置一起导出；旧版本存在 localStorage 中的列表迁移过去
+    invoke<{ blocked_apps: string[] }>('get_user_config')
+      .then((config) => {
+        const saved = localStorage.getItem('blockedApps')
+        if (config.blocked_apps.length === 0 && saved) {
+          setBlockedApps(JSON.parse(saved))
+        } else {
+          setBlockedApps(config.blocked_apps)
+        }
+      })
+      .catch((error) => console.error('Failed to load blocked apps:', error))
+      .finally(() => setLoaded(true))
   }, [])
 
   useEffect(() => {
+    if (!loaded) return
     localStorage.setItem('blockedApps', JSON.stringify(blockedApps))
-  }, [blockedApps])
+    invoke('set_blocked_apps', { apps: blockedApps }).catch((error) =>
+      console.error('Failed to save blocked apps:', error)
+    )
+  }, [blockedApps, loaded])
 
   useEffect(() => {
     let interval: ReturnType<typeof setInterval>
@@ -49,7 +69,7 @@ export function AppBlocker() {
             const result = await invoke<ProcessResult>('is_app_running_cmd', { name: appName })
             if (result.success && result.message.includes('is running')) {
               await invoke<ProcessResult>('kill_app_processes', { name: appName })
-              await invoke('add_block', { website: false })
+              await invoke('add_block', { website: false, target: appName })
             }
           } catch (error) {
             console.error(`Error monitoring ${appName}:`, error)
@@ -87,10 +107,10 @@ export function AppBlocker() {
     }
   }
 
-  const killApp = async (pid: number) => {
+  const killApp = async (pid: number, name: string) => {
     try {
       await invoke<ProcessResult>('kill_process_cmd', { pid })
-      await invoke('add_block', { website: false })
+      await invoke('add_block', { website: false, target: name })
       checkRunningApps()
     } catch (error) {
       console.error('Failed to kill process:', error)
@@ -235,7 +255,7 @@ export function AppBlocker() {
                   <Button
                     variant="destructive"
                     size="sm"
-                    onClick={() => killApp(app.pid)}
+                    onClick={() => killApp(app.pid, app.name)}
                   >
                     <Trash2 className="w-4 h-4 mr-2" />
                     关闭
@@ -39,15 +39,25 @@ export function FocusMode() {
       setFocusState(event.payload)
     })
 
-    const unlistenComplete = listen<{ duration: number }>('focus-complete', async (event) => {
-      await invoke('add_focus_time', { seconds: event.payload.duration })
-      await invoke('add_block', { website: false })
+    // 专注时长由后端在会话完成时记录
+    const unlistenComplete = listen<{ duration: number }>('focus-complete', () => {
       setFocusState(prev => ({ ...prev, is_running: false }))
     })
 
+    const unlistenIdle = listen<{ elapsed: number }>('focus-idle', (event) => {
+      setFocusState(prev => ({ ...prev, is_running: false, elapsed: event.payload.elapsed }))
+    })
+
+    const unlistenActive = listen('focus-active', async () => {
+      const state = await invoke<FocusState>('get_focus_state')
+      setFocusState(state)
+    })
+
     return () => {
       unlisten.then(f => f())
       unlistenComplete.then(f => f())
+      unlistenIdle.then(f => f())
+      unlistenActive.then(f => f())
     }
   }, [])
 
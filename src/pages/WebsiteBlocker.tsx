@@ -60,7 +60,6 @@ export function WebsiteBlocker() {
         setBlockedWebsites(prev => [...prev, newWebsite.trim()])
         setNewWebsite('')
         setMessage({ type: 'success', text: '网站已添加到黑名单' })
-        await invoke('add_block', { website: true })
       } else {
         setMessage({ type: 'error', text: result.message })
       }